@@ -0,0 +1,73 @@
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Defaults for the command line options, read from `prmd/config.toml` in the config
+/// directory: $XDG_CONFIG_HOME or ~/.config, or %APPDATA% on Windows. The keys are the
+/// names of the long options, e.g.
+///
+/// ```toml
+/// width = 100
+/// theme = "light"
+/// hyperlinks = true
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub plain: bool,
+    pub width: Option<usize>,
+    pub theme: Option<String>,
+    pub color_emphasis: bool,
+    pub heading_markers: bool,
+    pub number_headings: bool,
+    pub center_headings: bool,
+    pub setext_headings: bool,
+    pub title_from_frontmatter: bool,
+    pub borders: bool,
+    pub align_numbers: bool,
+    pub no_highlight: bool,
+    pub line_numbers: bool,
+    pub boxed_code: bool,
+    pub no_images: bool,
+    pub task_progress: bool,
+    pub hyperlinks: bool,
+    pub references: bool,
+    pub raw_html: bool,
+    pub escape_html: bool,
+    pub preserve_breaks: bool,
+    pub compact: bool,
+    pub pager: bool,
+}
+
+impl Config {
+    /// Reads the config file, or returns the defaults if there isn't one.
+    pub fn load() -> Result<Config, String> {
+        let Some(path) = path() else {
+            return Ok(Config::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(error) => {
+                let message = crate::io_error_message(&error);
+                return Err(format!("{}: {}", path.display(), message));
+            }
+        };
+        toml::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+/// Where the config file would be, if there's a config directory to look in
+fn path() -> Option<PathBuf> {
+    let directory = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|directory| !directory.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }?;
+    Some(directory.join("prmd").join("config.toml"))
+}
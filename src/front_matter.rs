@@ -0,0 +1,51 @@
+/// The `title` of YAML front matter between `---` lines or TOML front matter between `+++`
+/// lines, if it has a title on a line of its own. Only plain and quoted one-line values are
+/// understood; anything else is taken as not having a title.
+pub(crate) fn title(front_matter: &str) -> Option<String> {
+    let mut lines = front_matter.lines();
+    let toml = lines.next()?.trim_end() == "+++";
+    for line in lines {
+        if toml && line.trim_start().starts_with('[') {
+            // Keys after a table header belong to the table
+            return None;
+        }
+        let separator = if toml { '=' } else { ':' };
+        let Some((key, value)) = line.split_once(separator) else {
+            continue;
+        };
+        // YAML keys with leading space belong to a mapping nested in another key
+        let key = if toml { key.trim() } else { key.trim_end() };
+        if key == "title" {
+            return value_text(value.trim(), toml).filter(|title| !title.is_empty());
+        }
+    }
+    None
+}
+
+fn value_text(value: &str, toml: bool) -> Option<String> {
+    let mut chars = value.chars();
+    match chars.next()? {
+        quote @ ('"' | '\'') => {
+            let mut text = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    c if c == quote => return Some(text),
+                    '\\' if quote == '"' => text.push(chars.next()?),
+                    c => text.push(c),
+                }
+            }
+            None
+        }
+        // YAML block scalars and TOML arrays, tables and multi-line strings
+        '|' | '>' | '[' | '{' => None,
+        // TOML strings are always quoted
+        _ if toml => None,
+        _ => {
+            let text = match value.find(" #") {
+                Some(index) => &value[..index],
+                None => value,
+            };
+            Some(text.trim_end().to_string())
+        }
+    }
+}
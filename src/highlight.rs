@@ -0,0 +1,99 @@
+#[cfg(feature = "highlight")]
+use crate::theme::nearest_256_color;
+#[cfg(feature = "highlight")]
+use ansi_term::{Color, Style};
+#[cfg(feature = "highlight")]
+use std::sync::OnceLock;
+#[cfg(feature = "highlight")]
+use syntect::highlighting::{Highlighter, ScopeSelectors, ThemeSet};
+#[cfg(feature = "highlight")]
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+#[cfg(feature = "highlight")]
+use syntect::util::LinesWithEndings;
+
+/// Highlights `code` as the language named by the first word of a code block's info string,
+/// returning one ANSI-colored string per line, or `None` if the language isn't recognized.
+/// Comments are styled with the theme's `code_comment` style. Without `truecolor`, the
+/// highlighting colors are approximated with the 256-color palette.
+#[cfg(feature = "highlight")]
+pub(crate) fn highlight_lines(
+    info: &str,
+    code: &str,
+    theme: &crate::Theme,
+    truecolor: bool,
+) -> Option<Vec<String>> {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+
+    let language = info.split_whitespace().next()?;
+    let syntaxes = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+    let syntax = syntaxes.find_syntax_by_token(language)?;
+    let highlighter = Highlighter::new(
+        THEMES
+            .get_or_init(ThemeSet::load_defaults)
+            .themes
+            .get("base16-ocean.dark")?,
+    );
+    let comment: ScopeSelectors = "comment".parse().ok()?;
+    let mut parser = ParseState::new(syntax);
+    let mut scopes = ScopeStack::new();
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let operations = parser.parse_line(line, syntaxes).ok()?;
+            let mut escaped = String::default();
+            let mut start = 0;
+            // Set after a comment's style, which has to be reset before the next token
+            let mut styled = false;
+            let ends = operations
+                .iter()
+                .map(|(end, operation)| (*end, Some(operation)));
+            for (end, operation) in ends.chain([(line.len(), None)]) {
+                let text = &line[start..end];
+                if !text.is_empty() {
+                    let color = highlighter.style_for_stack(scopes.as_slice()).foreground;
+                    let color = Color::RGB(color.r, color.g, color.b);
+                    let color = if truecolor {
+                        color
+                    } else {
+                        nearest_256_color(color)
+                    };
+                    if comment.does_match(scopes.as_slice()).is_some() {
+                        let style = Style {
+                            foreground: theme.code_comment.foreground.or(Some(color)),
+                            background: theme.code_block.background,
+                            ..theme.code_comment
+                        };
+                        escaped.push_str(&format!("\x1b[0m{}{}", style.prefix(), text));
+                        styled = true;
+                    } else if styled {
+                        let style = Style {
+                            background: theme.code_block.background,
+                            ..Style::new().fg(color)
+                        };
+                        escaped.push_str(&format!("\x1b[0m{}{}", style.prefix(), text));
+                        styled = false;
+                    } else {
+                        // Only the foreground is set, and never reset, so the code block's
+                        // background shows through
+                        escaped.push_str(&format!("{}{}", Style::new().fg(color).prefix(), text));
+                    }
+                }
+                if let Some(operation) = operation {
+                    scopes.apply(operation).ok()?;
+                }
+                start = end;
+            }
+            Some(escaped.trim_end_matches(['\n', '\r']).to_string())
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "highlight"))]
+pub(crate) fn highlight_lines(
+    _info: &str,
+    _code: &str,
+    _theme: &crate::Theme,
+    _truecolor: bool,
+) -> Option<Vec<String>> {
+    None
+}
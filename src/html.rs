@@ -0,0 +1,173 @@
+/// How HTML blocks are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum HtmlMode {
+    /// Drop the tags and render the text inside them
+    #[default]
+    Strip,
+    /// Print the markup as written, except that tags that could do harm if the text were
+    /// shown as HTML, like `<script>` and `<iframe>`, are escaped
+    Escape,
+    /// Print the markup exactly as written
+    Raw,
+}
+
+/// The tags that GitHub's tagfilter extension escapes, as they change how the HTML around
+/// them is interpreted
+const FILTERED_TAGS: [&str; 9] = [
+    "title",
+    "textarea",
+    "style",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "script",
+    "plaintext",
+];
+
+/// The elements that aren't text, so stripping drops them along with their contents
+const DROPPED_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// Escapes the `<` of each opening or closing tag in [`FILTERED_TAGS`], leaving the rest of
+/// the HTML as it is.
+pub(crate) fn filter_tags(html: &str) -> String {
+    let mut filtered = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        filtered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let name = tag_name(rest);
+        if FILTERED_TAGS.contains(&name.as_str()) {
+            filtered.push_str("&lt;");
+        } else {
+            filtered.push('<');
+        }
+        rest = &rest[1..];
+    }
+    filtered.push_str(rest);
+    filtered
+}
+
+/// Removes the tags and comments from `html`, keeping the text between them except in
+/// `<script>` and `<style>` elements. Line breaks are kept for `<br>` and block-level
+/// elements, and `<hr>` is replaced by `thematic_break`.
+/// Entities are decoded afterwards, so `&lt;` in the source can't start a tag.
+pub(crate) fn strip_tags(html: &str, thematic_break: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let name = tag_name(rest);
+        let end = match rest.find('>') {
+            Some(end) if !name.is_empty() => end,
+            // Not a tag, just a less-than sign
+            _ => {
+                text.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        match name.as_str() {
+            // Scripts and style sheets aren't text, so they're dropped along with their tags
+            name if DROPPED_ELEMENTS.contains(&name) && !rest.starts_with("</") => {
+                let close = format!("</{}", name);
+                rest = match rest.to_ascii_lowercase().find(&close) {
+                    Some(index) => &rest[index..],
+                    None => "",
+                };
+                continue;
+            }
+            "br" => text.push('\n'),
+            "hr" => {
+                text.push('\n');
+                text.push_str(thematic_break);
+            }
+            "address" | "article" | "aside" | "blockquote" | "dd" | "details" | "div" | "dl"
+            | "dt" | "figcaption" | "figure" | "footer" | "h1" | "h2" | "h3" | "h4" | "h5"
+            | "h6" | "header" | "li" | "ol" | "p" | "pre" | "section" | "summary" | "table"
+            | "tr" | "ul" => text.push('\n'),
+            _ => {}
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    decode_entities(&text)
+}
+
+/// If the inline HTML is the opening tag of an element in [`DROPPED_ELEMENTS`], the
+/// element's name.
+pub(crate) fn dropped_element(html: &str) -> Option<String> {
+    let name = tag_name(html);
+    (html.starts_with('<') && !html.starts_with("</") && DROPPED_ELEMENTS.contains(&name.as_str()))
+        .then_some(name)
+}
+
+/// Whether the inline HTML is the closing tag of the element `name`.
+pub(crate) fn closes(html: &str, name: &str) -> bool {
+    html.starts_with("</") && tag_name(html) == name
+}
+
+/// If the HTML block opens a `<details>` element, returns the text of its `<summary>`, or
+/// "Details" if it has none, and the HTML that follows the summary, without the closing
+/// `</details>` if the block ends with it.
+pub(crate) fn details(html: &str) -> Option<(String, &str)> {
+    let html = html.trim();
+    if !html.starts_with('<') || tag_name(html) != "details" || html.starts_with("</") {
+        return None;
+    }
+    let mut rest = &html[html.find('>')? + 1..];
+    let mut summary = String::from("Details");
+    let lowercase = rest.to_ascii_lowercase();
+    if let Some(start) = lowercase.find("<summary") {
+        let end = start + lowercase[start..].find("</summary>")?;
+        let text = &rest[start..end];
+        let text = &text[text.find('>').map_or(text.len(), |index| index + 1)..];
+        summary = strip_tags(text, "")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        rest = &rest[end + "</summary>".len()..];
+    }
+    let lowercase = rest.to_ascii_lowercase();
+    if lowercase.trim_end().ends_with("</details>") {
+        rest = &rest[..lowercase.trim_end().len() - "</details>".len()];
+    }
+    Some((summary, rest))
+}
+
+/// How many more `<details>` elements the HTML opens than it closes.
+pub(crate) fn details_depth(html: &str) -> isize {
+    let lowercase = html.to_ascii_lowercase();
+    let opened = lowercase
+        .match_indices("<details")
+        .filter(|(index, _)| tag_name(&lowercase[*index..]) == "details")
+        .count();
+    let closed = lowercase.matches("</details").count();
+    opened as isize - closed as isize
+}
+
+/// Replaces HTML entities such as `&amp;` and `&#169;` with the characters they stand for.
+pub(crate) fn decode_entities(text: &str) -> String {
+    html_escape::decode_html_entities(text).into_owned()
+}
+
+/// The lowercased element name of the tag at the start of `text`, or an empty string if it
+/// doesn't start with one.
+fn tag_name(text: &str) -> String {
+    text[1..]
+        .trim_start_matches('/')
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
@@ -1,197 +1,1503 @@
-use ansi_term::{ANSIString, Color, Style};
+use ansi_term::Style;
 use comrak::arena_tree::Node;
 use comrak::nodes::{
-    Ast, ListDelimType, ListType, NodeCodeBlock, NodeHeading, NodeHtmlBlock, NodeList, NodeTable,
-    NodeValue, TableAlignment,
+    Ast, LineColumn, ListDelimType, ListType, NodeCodeBlock, NodeHeading, NodeHtmlBlock, NodeLink,
+    NodeList, NodeMath, NodeTable, NodeValue, TableAlignment,
 };
 use comrak::{Arena, Options};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+mod front_matter;
+mod highlight;
+mod html;
+mod renderer;
+mod stats;
+#[cfg(feature = "serde")]
+mod style_spec;
+mod superscript;
+mod theme;
+#[cfg(feature = "theme-file")]
+mod theme_file;
+
+pub use html::HtmlMode;
+pub use renderer::Renderer;
+pub use stats::{document_stats, DocumentStats};
+pub use theme::Theme;
+#[cfg(feature = "theme-file")]
+pub use theme_file::ThemeError;
+
+/// Settings that control how Markdown is rendered to text. With the `serde` feature they can
+/// be serialized, and deserialized with the defaults filling in any that are missing.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct RenderOptions {
+    /// Render without ANSI formatting
+    pub plain: bool,
+    /// Wrap paragraphs to this many columns
+    pub width: Option<usize>,
+    /// Leave out link and image URLs, code block and blockquote decorations and thematic
+    /// breaks, keeping just the text
+    pub text_only: bool,
+    /// Keep the line breaks within paragraphs instead of joining the lines, wrapping each
+    /// line on its own
+    pub preserve_breaks: bool,
+    /// Indent the whole document by this many columns, which come out of the `width`
+    pub left_margin: usize,
+    /// Leave the newlines off the end of the text, e.g. for showing it in a widget that adds
+    /// its own spacing
+    pub trim_trailing_newlines: bool,
+    /// Leave out the blank lines between blocks, e.g. for short documents in a small
+    /// terminal; lines within code blocks are kept as they are
+    pub compact: bool,
+    /// Render links as OSC 8 terminal hyperlinks instead of appending the URL
+    pub hyperlinks: bool,
+    /// Prefix headings with `#` markers showing their level
+    pub heading_markers: bool,
+    /// Prefix headings with section numbers like 1, 1.1 and 1.2
+    pub number_headings: bool,
+    /// Center headings within the `width`
+    pub center_headings: bool,
+    /// Underline level 1 and 2 headings with a row of `=` or `-` as wide as the heading,
+    /// like setext headings in Markdown
+    pub setext_headings: bool,
+    /// Show the `title` of the document's front matter, which is otherwise left out, as a
+    /// level 1 heading
+    pub title_from_frontmatter: bool,
+    /// Draw box borders around and between table cells
+    pub table_borders: bool,
+    /// Wrap table cells wider than this many columns onto multiple lines
+    pub table_max_column_width: Option<usize>,
+    /// Right-align table columns without an alignment of their own when every cell below
+    /// the header, other than empty ones, is a number
+    pub table_align_numbers: bool,
+    /// Syntax highlight fenced code blocks whose language is recognized (requires the
+    /// `highlight` feature)
+    pub highlight: bool,
+    /// Prefix each code block line with its line number
+    pub code_line_numbers: bool,
+    /// Indent each level of nested lists by this many columns
+    pub list_indent: usize,
+    /// Put a line like `(3/5 done)` above each list whose items are all tasks, counting
+    /// its own items but not those of lists nested in them
+    pub task_progress: bool,
+    /// The bullets for unordered lists, one per nesting level, repeating from the first
+    /// for lists nested deeper than there are bullets
+    pub bullets: Vec<String>,
+    /// Expand tabs to stops this many columns apart
+    pub tab_width: usize,
+    /// Draw a box around code blocks in plain mode, padding every line to the same width
+    pub code_block_boxed: bool,
+    /// Show images as their alt text and URL; otherwise they're left out
+    pub images: bool,
+    /// Put in front of an image's alt text to tell it apart from a link
+    pub image_marker: String,
+    /// Number links and list their URLs in a References section at the end
+    pub link_references: bool,
+    /// The character repeated across the width for thematic breaks, or `None` for a `¶`
+    pub thematic_break: Option<char>,
+    /// Whether HTML blocks are stripped down to their text, printed with harmful tags
+    /// escaped, or printed as written
+    pub html: HtmlMode,
+    /// Use 24-bit RGB colors as they are; otherwise they're replaced by the nearest of the
+    /// 256 indexed colors
+    pub truecolor: bool,
+    /// The styles used when not rendering plain text
+    pub theme: Theme,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            plain: false,
+            width: None,
+            text_only: false,
+            preserve_breaks: false,
+            left_margin: 0,
+            trim_trailing_newlines: false,
+            compact: false,
+            hyperlinks: false,
+            heading_markers: false,
+            number_headings: false,
+            center_headings: false,
+            setext_headings: false,
+            title_from_frontmatter: false,
+            table_borders: false,
+            table_max_column_width: None,
+            table_align_numbers: false,
+            highlight: true,
+            code_line_numbers: false,
+            list_indent: 4,
+            task_progress: false,
+            bullets: ["•", "◦", "▪", "‣", "⁃"].map(String::from).to_vec(),
+            tab_width: 4,
+            code_block_boxed: false,
+            images: true,
+            image_marker: String::from("🖼 "),
+            link_references: false,
+            thematic_break: Some('─'),
+            html: HtmlMode::default(),
+            truecolor: true,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl RenderOptions {
+    fn as_plain(&self) -> RenderOptions {
+        RenderOptions {
+            plain: true,
+            ..self.clone()
+        }
+    }
+}
+
+/// Converts Markdown to text, with ANSI formatting unless `plain` is set.
 pub fn markdown_to_text(md: &str, plain: bool) -> String {
+    markdown_to_text_with(
+        md,
+        &RenderOptions {
+            plain,
+            ..RenderOptions::default()
+        },
+    )
+}
+
+/// Converts Markdown to text using the given options.
+pub fn markdown_to_text_with(md: &str, render_options: &RenderOptions) -> String {
+    let (text, errors) = render_to_string(md, render_options);
+    warn(&errors);
+    text
+}
+
+/// Extracts the readable text from Markdown, without formatting, URLs or decorations, e.g.
+/// for indexing or search.
+pub fn markdown_to_plaintext(md: &str) -> String {
+    markdown_to_text_with(
+        md,
+        &RenderOptions {
+            plain: true,
+            text_only: true,
+            image_marker: String::default(),
+            ..RenderOptions::default()
+        },
+    )
+}
+
+/// Like `markdown_to_text_with`, but returns the nodes that couldn't be rendered along with
+/// the text instead of printing a warning for each. The text still has a 💔 placeholder in
+/// place of each of them.
+pub fn markdown_to_text_diagnostics(
+    md: &str,
+    render_options: &RenderOptions,
+) -> (String, Vec<RenderError>) {
+    render_to_string(md, render_options)
+}
+
+/// Like `markdown_to_text_with`, but returns the first unexpected node as an error
+/// instead of printing a warning and rendering a placeholder.
+pub fn try_markdown_to_text(
+    md: &str,
+    render_options: &RenderOptions,
+) -> Result<String, RenderError> {
+    let (text, errors) = render_to_string(md, render_options);
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(text),
+    }
+}
+
+/// Converts Markdown to text, parsing it with the given comrak options instead of the
+/// defaults from [`comrak_options`].
+///
+/// The renderer understands these kinds of nodes:
+///
+/// - blocks: `Paragraph`, `Heading`, `List`, `Item`, `TaskItem`, `CodeBlock`, `BlockQuote`
+///   (including GitHub alerts), `ThematicBreak`, `HtmlBlock`, `Table`, `DescriptionList`,
+///   `FootnoteDefinition` and `FrontMatter`, which is left out unless its title is shown
+/// - inlines: `Text`, `Emph`, `Strong`, `Underline`, `Strikethrough`, `Code`, `Link`, `Image`,
+///   `Superscript`, `FootnoteReference`, `Math`, `HtmlInline`, `WikiLink`, `SoftBreak` and
+///   `LineBreak`
+///
+/// Anything else that an enabled extension produces, such as `SpoileredText`, is
+/// rendered as a 💔 placeholder and reported on stderr, or logged with the `log` feature.
+///
+/// Footnotes are numbered in the order they're first referenced, not the order they're
+/// defined in, and every reference to one has the same number. They're listed once each in
+/// a Footnotes section at the end, in that order; definitions that are never referenced are
+/// left out.
+pub fn markdown_to_text_with_options(
+    md: &str,
+    options: &Options,
+    render_options: &RenderOptions,
+) -> String {
+    let mut buffer: Vec<u8> = vec![];
+    let errors = render_with_comrak_options(md, options, render_options, &mut buffer)
+        .expect("Couldn't write to buffer");
+    warn(&errors);
+    String::from_utf8(buffer).expect("Rendered text isn't UTF-8")
+}
+
+/// Renders the Markdown to `out` one top-level block at a time.
+pub fn write_markdown<W: Write>(md: &str, plain: bool, out: &mut W) -> io::Result<()> {
+    write_markdown_with(
+        md,
+        &RenderOptions {
+            plain,
+            ..RenderOptions::default()
+        },
+        out,
+    )
+}
+
+/// Like `write_markdown`, but renders with the given options.
+pub fn write_markdown_with<W: Write>(
+    md: &str,
+    render_options: &RenderOptions,
+    out: &mut W,
+) -> io::Result<()> {
+    let errors = render(md, render_options, out)?;
+    warn(&errors);
+    Ok(())
+}
+
+/// Renders the Markdown one top-level block at a time, e.g. to show a long document
+/// progressively. Joined together, the blocks are the text `markdown_to_text_with` returns.
+pub fn render_blocks(md: &str, render_options: &RenderOptions) -> impl Iterator<Item = String> {
+    let mut blocks = BlockList::default();
+    let errors = render(md, render_options, &mut blocks).expect("Couldn't write to buffer");
+    warn(&errors);
+    blocks.0.into_iter()
+}
+
+/// Like `write_markdown_with`, but returns the nodes that couldn't be rendered instead of
+/// printing a warning for each, so the caller can decide what to do about them.
+pub fn write_markdown_diagnostics<W: Write>(
+    md: &str,
+    render_options: &RenderOptions,
+    out: &mut W,
+) -> io::Result<Vec<RenderError>> {
+    render(md, render_options, out)
+}
+
+/// Reports the nodes that couldn't be rendered: as `log` warnings with the `log` feature, so
+/// the application's logger decides where they go, and on stderr without it.
+pub(crate) fn warn(errors: &[RenderError]) {
+    #[cfg(feature = "log")]
+    errors.iter().for_each(|error| log::warn!("{}", error));
+    #[cfg(not(feature = "log"))]
+    errors.iter().for_each(|error| eprintln!("💔 {}", error));
+}
+
+fn render_to_string(md: &str, render_options: &RenderOptions) -> (String, Vec<RenderError>) {
+    let mut buffer: Vec<u8> = vec![];
+    let errors = render(md, render_options, &mut buffer).expect("Couldn't write to buffer");
+    let text = String::from_utf8(buffer).expect("Rendered text isn't UTF-8");
+    (text, errors)
+}
+
+fn render<W: Write>(
+    md: &str,
+    render_options: &RenderOptions,
+    out: &mut W,
+) -> io::Result<Vec<RenderError>> {
+    render_with_comrak_options(md, &comrak_options(), render_options, out)
+}
+
+fn render_with_comrak_options<W: Write>(
+    md: &str,
+    options: &Options,
+    render_options: &RenderOptions,
+    out: &mut W,
+) -> io::Result<Vec<RenderError>> {
     let arena = Arena::new();
+    let root = parse_document(&arena, md, options);
+    let mut render_options = render_options.clone();
+    // The margin comes out of the width the blocks are wrapped to
+    render_options.width = render_options
+        .width
+        .map(|width| width.saturating_sub(render_options.left_margin));
+    if !render_options.truecolor {
+        render_options.theme = render_options.theme.to_256_colors();
+    }
+    let context = RenderContext::new(render_options);
+    write_ast(root, &context, out)?;
+    Ok(context.errors.into_inner())
+}
+
+/// The parsing options used by the other entry points: CommonMark plus the table,
+/// strikethrough, task list, footnote, math and description list extensions.
+pub fn comrak_options() -> Options<'static> {
     let mut options = Options::default();
     options.extension.table = true;
     options.extension.strikethrough = true;
-    let root = comrak::parse_document(&arena, md, &options);
-    ast_to_text(root, plain)
+    options.extension.tasklist = true;
+    options.extension.footnotes = true;
+    options.extension.math_dollars = true;
+    options.extension.math_code = true;
+    options.extension.description_lists = true;
+    options.extension.front_matter_delimiter = Some(String::from("---"));
+    options
 }
 
-fn ast_to_text<'a>(root: &'a Node<'a, RefCell<Ast>>, plain: bool) -> String {
-    fn node_children_to_text<'a>(node: &'a Node<'a, RefCell<Ast>>, plain: bool) -> String {
-        node.children()
-            .map(|child| text_node_to_text(child, plain))
-            .collect::<Vec<String>>()
-            .join("")
+/// Parses the document, also taking TOML front matter between `+++` lines when the options
+/// take YAML front matter between `---` lines, which comrak only takes one of.
+fn parse_document<'a>(
+    arena: &'a Arena<Node<'a, RefCell<Ast>>>,
+    md: &str,
+    options: &Options,
+) -> &'a Node<'a, RefCell<Ast>> {
+    let delimiter = options.extension.front_matter_delimiter.as_deref();
+    if delimiter == Some("---") && md.trim_start_matches('\u{feff}').starts_with("+++") {
+        let mut options = options.clone();
+        options.extension.front_matter_delimiter = Some(String::from("+++"));
+        return comrak::parse_document(arena, md, &options);
     }
-    fn text_node_to_text<'a>(text_node: &'a Node<'a, RefCell<Ast>>, plain: bool) -> String {
-        match &text_node.data.borrow().value {
-            NodeValue::Emph => {
-                let text = node_children_to_text(text_node, plain);
-                if plain {
-                    text
-                } else {
-                    Style::new().italic().paint(text).to_string()
-                }
+    comrak::parse_document(arena, md, options)
+}
+
+/// Lists the document's headings as a table of contents: each heading's level and its text
+/// without any formatting.
+pub fn build_toc(md: &str) -> Vec<(usize, String)> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, md, &comrak_options());
+    root.descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::Heading(heading) => Some((usize::from(heading.level), plain_text(node))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a table cell's text is a number, like `-12`, `1,234.50`, `$5`, `3.5%` or `1e-3`
+fn is_number(text: &str) -> bool {
+    let text = text.strip_prefix(['-', '+', '−']).unwrap_or(text);
+    let text = text.strip_prefix(['$', '€', '£', '¥']).unwrap_or(text);
+    let text = text.strip_suffix('%').unwrap_or(text);
+    let digits = text.replace(',', "");
+    digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits.chars().any(|c| c.is_ascii_digit())
+        && digits.parse::<f64>().is_ok()
+}
+
+/// The text inside an inline node, without formatting, link URLs or markup
+fn plain_text<'a>(node: &'a Node<'a, RefCell<Ast>>) -> String {
+    node.children()
+        .map(|child| match &child.data.borrow().value {
+            NodeValue::Text(text) => text.clone(),
+            NodeValue::Code(code) => code.literal.clone(),
+            NodeValue::SoftBreak | NodeValue::LineBreak => String::from(" "),
+            _ => plain_text(child),
+        })
+        .collect()
+}
+
+/// A node the renderer doesn't handle, and where it is in the Markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderError {
+    /// The `NodeValue` variant that the renderer doesn't handle
+    pub node: String,
+    /// The kind of node it was found in
+    pub parent: &'static str,
+    /// The line in the Markdown source where the node starts
+    pub line: usize,
+    /// The column in the Markdown source where the node starts
+    pub column: usize,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unexpected {} in {} node at line {}, column {}",
+            self.node, self.parent, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+struct RenderContext {
+    options: RenderOptions,
+    errors: RefCell<Vec<RenderError>>,
+    /// The distinct link URLs in the order they're first referenced
+    links: RefCell<Vec<String>>,
+    /// The combined styles of the inline nodes being rendered, painted on their text
+    style: Cell<Style>,
+}
+
+impl RenderContext {
+    fn new(options: RenderOptions) -> RenderContext {
+        RenderContext {
+            options,
+            errors: RefCell::new(vec![]),
+            links: RefCell::new(vec![]),
+            style: Cell::new(Style::new()),
+        }
+    }
+
+    /// A context for measuring plain text; anything it records is discarded.
+    fn as_plain(&self) -> RenderContext {
+        let context = RenderContext::new(self.options.as_plain());
+        // Start from the same link numbers so measured references are the same width
+        context.links.replace(self.links.borrow().clone());
+        context
+    }
+
+    /// A context for measuring text rendered exactly as it will be displayed; anything it
+    /// records is discarded.
+    fn for_measuring(&self) -> RenderContext {
+        let context = RenderContext::new(self.options.clone());
+        context.links.replace(self.links.borrow().clone());
+        context.style.set(self.style.get());
+        context
+    }
+
+    /// Splits text into lines, wrapping them if a width is set. `used` is the number of
+    /// columns already taken up by indentation or markers in front of each line.
+    fn wrap_lines(&self, text: &str, used: usize) -> Vec<String> {
+        match self.options.width {
+            Some(width) => wrap_styled_text(text, width.saturating_sub(used)),
+            None => text.lines().map(String::from).collect(),
+        }
+    }
+
+    /// What follows the last line of a block: a blank line to set it apart from the next
+    /// block, or nothing when compact.
+    fn block_end(&self) -> &'static str {
+        if self.options.compact {
+            "\n"
+        } else {
+            "\n\n"
+        }
+    }
+
+    /// Returns the reference number for the URL, registering it if it's new.
+    fn link_reference(&self, url: &str) -> usize {
+        let mut links = self.links.borrow_mut();
+        match links.iter().position(|link| link == url) {
+            Some(index) => index + 1,
+            None => {
+                links.push(url.to_string());
+                links.len()
             }
-            NodeValue::Strong => {
-                let text = node_children_to_text(text_node, plain);
-                if plain {
-                    text
-                } else {
-                    Style::new().bold().paint(text).to_string()
+        }
+    }
+
+    /// Runs `render` with a context whose width is narrowed by the `used` columns that a block
+    /// nested inside another is indented by, keeping the links and errors it records.
+    fn nested<T>(&self, used: usize, render: impl FnOnce(&RenderContext) -> T) -> T {
+        let mut options = self.options.clone();
+        options.width = options.width.map(|width| width.saturating_sub(used));
+        let context = RenderContext::new(options);
+        context.links.replace(self.links.take());
+        context.style.set(self.style.get());
+        let result = render(&context);
+        self.links.replace(context.links.into_inner());
+        self.errors.borrow_mut().extend(context.errors.into_inner());
+        result
+    }
+
+    /// Runs `render` with `style` combined with the styles of the inline nodes it's inside of.
+    fn styled<T>(&self, style: Style, render: impl FnOnce() -> T) -> T {
+        let outer = self.style.get();
+        if !self.options.plain {
+            self.style.set(combine_styles(outer, style));
+        }
+        let result = render();
+        self.style.set(outer);
+        result
+    }
+
+    /// Paints the text of an inline leaf node with the styles it's inside of.
+    fn paint(&self, text: &str) -> String {
+        let style = self.style.get();
+        if self.options.plain || style == Style::new() || text.is_empty() {
+            text.to_string()
+        } else {
+            style.paint(text).to_string()
+        }
+    }
+
+    /// Records an unexpected node and returns the placeholder rendered in its place.
+    fn unexpected_node<'a>(
+        &self,
+        node: &'a Node<'a, RefCell<Ast>>,
+        parent: &'static str,
+    ) -> String {
+        let ast = node.data.borrow();
+        let debug = format!("{:?}", ast.value);
+        let name = debug
+            .split(|c: char| !c.is_alphanumeric())
+            .next()
+            .unwrap_or_default();
+        self.errors.borrow_mut().push(RenderError {
+            node: name.to_string(),
+            parent,
+            line: ast.sourcepos.start.line,
+            column: ast.sourcepos.start.column,
+        });
+        format!("💔 Unexpected child in {} node", parent)
+    }
+}
+
+/// Paints text that may already have styles in it, reopening `style` after each of their
+/// resets so that the text following a nested style is still painted.
+fn paint_nested(style: Style, text: &str) -> String {
+    let prefix = style.prefix().to_string();
+    if prefix.is_empty() {
+        return text.to_string();
+    }
+    let reset = format!("\x1b[0m{}", prefix);
+    style.paint(text.replace("\x1b[0m", &reset)).to_string()
+}
+
+/// `inner` on top of `outer`: the attributes of both, and the colors of `inner` where it has
+/// them.
+fn combine_styles(outer: Style, inner: Style) -> Style {
+    Style {
+        foreground: inner.foreground.or(outer.foreground),
+        background: inner.background.or(outer.background),
+        is_bold: outer.is_bold || inner.is_bold,
+        is_dimmed: outer.is_dimmed || inner.is_dimmed,
+        is_italic: outer.is_italic || inner.is_italic,
+        is_underline: outer.is_underline || inner.is_underline,
+        is_blink: outer.is_blink || inner.is_blink,
+        is_reverse: outer.is_reverse || inner.is_reverse,
+        is_hidden: outer.is_hidden || inner.is_hidden,
+        is_strikethrough: outer.is_strikethrough || inner.is_strikethrough,
+    }
+}
+
+/// Joins neighboring runs of inline text painted with the same style by dropping the reset
+/// and repeated style between them.
+fn join_styled_runs(text: &str) -> String {
+    const RESET: &str = "\x1b[0m";
+    let mut joined = String::with_capacity(text.len());
+    // The style of the run being written
+    let mut open: Option<&str> = None;
+    let mut rest = text;
+    while let Some(start) = rest.find("\x1b[") {
+        joined.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest
+            .find('m')
+            .filter(|&end| rest[2..end].chars().all(|c| c.is_ascii_digit() || c == ';'))
+        else {
+            joined.push_str(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        };
+        let sequence = &rest[..=end];
+        rest = &rest[end + 1..];
+        if sequence == RESET {
+            if let Some(style) = open.filter(|style| rest.starts_with(style)) {
+                rest = &rest[style.len()..];
+                continue;
+            }
+            open = None;
+        } else {
+            open = Some(sequence);
+        }
+        joined.push_str(sequence);
+    }
+    joined.push_str(rest);
+    joined
+}
+
+/// The number of columns the text takes up on screen, not counting ANSI escape sequences.
+pub fn visible_width(text: &str) -> usize {
+    UnicodeWidthStr::width(strip_ansi(text).as_str())
+}
+
+/// The width of the widest line of rendered text, e.g. to size a box drawn around it.
+pub fn rendered_width(rendered: &str) -> usize {
+    rendered.lines().map(visible_width).max().unwrap_or(0)
+}
+
+/// Removes the ANSI escape sequences from styled text: CSI sequences like colors, OSC
+/// sequences like hyperlinks, and two-character escapes.
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        plain.push_str(&rest[..start]);
+        rest = &rest[start + escape_sequence_len(&rest[start..])..];
+    }
+    plain.push_str(rest);
+    plain
+}
+
+/// The length in bytes of the ANSI escape sequence at the start of `text`.
+fn escape_sequence_len(text: &str) -> usize {
+    let mut chars = text.char_indices().skip(1).peekable();
+    let end = match chars.next() {
+        // CSI: parameters and intermediates up to a final byte in @ to ~
+        Some((_, '[')) => chars.find(|(_, c)| ('\x40'..='\x7e').contains(c)),
+        // OSC: up to a BEL or an ESC \ string terminator
+        Some((_, ']')) => loop {
+            match chars.next() {
+                Some((index, '\x07')) => break Some((index, '\x07')),
+                Some((_, '\x1b')) => {
+                    if let Some(terminator) = chars.next_if(|(_, c)| *c == '\\') {
+                        break Some(terminator);
+                    }
                 }
+                Some(_) => {}
+                None => break None,
             }
-            NodeValue::Underline => {
-                let text = node_children_to_text(text_node, plain);
-                if plain {
-                    text
-                } else {
-                    Style::new().underline().paint(text).to_string()
+        },
+        other => other,
+    };
+    end.map_or(text.len(), |(index, c)| index + c.len_utf8())
+}
+
+/// Counts a heading at `level` and returns its section number, like "1.2". Deeper counters
+/// are reset, and levels skipped on the way down are numbered zero.
+fn next_section_number(counters: &mut Vec<usize>, level: u8) -> String {
+    let level = usize::from(level).max(1);
+    counters.resize(level, 0);
+    counters[level - 1] += 1;
+    counters
+        .iter()
+        .map(usize::to_string)
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// The math as it was written, with its delimiters
+fn math_source(math: &NodeMath) -> String {
+    match (math.dollar_math, math.display_math) {
+        (true, true) => format!("$${}$$", math.literal),
+        (true, false) => format!("${}$", math.literal),
+        (false, _) => format!("$`{}`$", math.literal),
+    }
+}
+
+/// Replaces tabs with spaces out to the next multiple of `tab_width` columns. ANSI escape
+/// sequences don't take up any columns.
+fn expand_tabs(text: &str, tab_width: usize) -> String {
+    if tab_width == 0 || !text.contains('\t') {
+        return text.to_string();
+    }
+    let mut expanded = String::new();
+    let mut column = 0;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '\x1b' {
+            let len = escape_sequence_len(rest);
+            expanded.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        match c {
+            '\t' => {
+                let spaces = tab_width - column % tab_width;
+                expanded.push_str(&" ".repeat(spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += c.width().unwrap_or(0);
+            }
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    expanded
+}
+
+/// Puts `indent` spaces in front of each line that isn't blank.
+fn indent_lines(text: &str, indent: usize) -> String {
+    let indent = " ".repeat(indent);
+    text.split_inclusive('\n')
+        .map(|line| match line {
+            "\n" => line.to_string(),
+            _ => format!("{}{}", indent, line),
+        })
+        .collect()
+}
+
+/// Wraps styled text to `width` visible columns, closing any ANSI styles still open at the
+/// end of a line and reopening them at the start of the next.
+fn wrap_styled_text(text: &str, width: usize) -> Vec<String> {
+    let mut active_styles: Vec<String> = vec![];
+    textwrap::wrap(text, width.max(1))
+        .iter()
+        .map(|line| {
+            let prefix = active_styles.concat();
+            let mut chars = line.char_indices().peekable();
+            while let Some((start, c)) = chars.next() {
+                if c != '\x1b' || chars.next_if(|(_, c)| *c == '[').is_none() {
+                    continue;
+                }
+                let end = loop {
+                    match chars.next() {
+                        Some((index, c)) if ('\x40'..='\x7e').contains(&c) => {
+                            break index + c.len_utf8()
+                        }
+                        Some(_) => continue,
+                        None => break line.len(),
+                    }
+                };
+                let sequence = &line[start..end];
+                if sequence == "\x1b[0m" || sequence == "\x1b[m" {
+                    active_styles.clear();
+                } else if sequence.ends_with('m') {
+                    active_styles.push(sequence.to_string());
                 }
             }
-            NodeValue::Strikethrough => {
-                let text = node_children_to_text(text_node, plain);
-                if plain {
-                    text
-                } else {
-                    Style::new().strikethrough().paint(text).to_string()
+            let suffix = if active_styles.is_empty() {
+                ""
+            } else {
+                "\x1b[0m"
+            };
+            format!("{}{}{}", prefix, line, suffix)
+        })
+        .collect()
+}
+
+/// Writes rendered blocks so that there's never more than one blank line in a row, however
+/// many newlines the blocks end and start with.
+struct BlockWriter<'w, W: Write> {
+    out: &'w mut W,
+    /// The number of newlines at the end of what's been written, up to two
+    trailing_newlines: usize,
+    /// Put in front of every line that isn't blank
+    margin: String,
+    /// Hold back the newlines at the end of each block until there's more text after them,
+    /// so the output doesn't end with any
+    trim_trailing_newlines: bool,
+    /// The newlines held back so far
+    held_newlines: usize,
+}
+
+impl<'w, W: Write> BlockWriter<'w, W> {
+    fn new(out: &'w mut W, options: &RenderOptions) -> BlockWriter<'w, W> {
+        // Nothing written yet counts as a blank line, so the output doesn't start with one
+        BlockWriter {
+            out,
+            trailing_newlines: 2,
+            margin: " ".repeat(options.left_margin),
+            trim_trailing_newlines: options.trim_trailing_newlines,
+            held_newlines: 0,
+        }
+    }
+
+    /// Writes the block, collapsing runs of blank lines within it unless it's preformatted.
+    fn write_block(&mut self, block: &str, preformatted: bool) -> io::Result<()> {
+        let block = if preformatted {
+            block.to_string()
+        } else {
+            collapse_blank_lines(block)
+        };
+        let leading = block.len() - block.trim_start_matches('\n').len();
+        let allowed = 2 - self.trailing_newlines;
+        let block = &block[leading - leading.min(allowed)..];
+        let trailing = block.len() - block.trim_end_matches('\n').len();
+        self.trailing_newlines = if trailing == block.len() {
+            (self.trailing_newlines + trailing).min(2)
+        } else {
+            trailing.min(2)
+        };
+        // The block is written in one piece, so a writer sees each block on its own
+        let mut text = String::with_capacity(block.len());
+        let block = if self.trim_trailing_newlines {
+            let body = block.trim_end_matches('\n');
+            if !body.is_empty() {
+                text.push_str(&"\n".repeat(self.held_newlines));
+                self.held_newlines = 0;
+            }
+            self.held_newlines += block.len() - body.len();
+            body
+        } else {
+            block
+        };
+        for line in block.split_inclusive('\n') {
+            if line != "\n" {
+                text.push_str(&self.margin);
+            }
+            text.push_str(line);
+        }
+        self.out.write_all(text.as_bytes())
+    }
+}
+
+/// Collects each block written by a `BlockWriter` as a string of its own.
+#[derive(Default)]
+struct BlockList(Vec<String>);
+
+impl Write for BlockList {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            self.0.push(String::from_utf8_lossy(buf).into_owned());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Replaces each run of three or more newlines with two.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut newlines = 0;
+    for c in text.chars() {
+        if c == '\n' {
+            newlines += 1;
+            if newlines > 2 {
+                continue;
+            }
+        } else {
+            newlines = 0;
+        }
+        collapsed.push(c);
+    }
+    collapsed
+}
+
+fn write_ast<'a, W: Write>(
+    root: &'a Node<'a, RefCell<Ast>>,
+    context: &RenderContext,
+    out: &mut W,
+) -> io::Result<()> {
+    /// The text of an inline container, like a paragraph or a table cell. Tabs are expanded
+    /// once it's all there, so that the columns before a tab count even if they're inside
+    /// emphasis or code.
+    fn node_children_to_text<'a>(
+        node: &'a Node<'a, RefCell<Ast>>,
+        context: &RenderContext,
+    ) -> String {
+        expand_tabs(
+            &inline_children_to_text(node, context),
+            context.options.tab_width,
+        )
+    }
+    fn inline_children_to_text<'a>(
+        node: &'a Node<'a, RefCell<Ast>>,
+        context: &RenderContext,
+    ) -> String {
+        inline_nodes_to_text(node.children(), context)
+    }
+    /// The text of a run of inline sibling nodes, like `inline_children_to_text` for a node's
+    /// children but for any part of them.
+    fn inline_nodes_to_text<'a>(
+        nodes: impl Iterator<Item = &'a Node<'a, RefCell<Ast>>>,
+        context: &RenderContext,
+    ) -> String {
+        let mut text = String::default();
+        let mut after_left_out_image = false;
+        // A stripped `<script>` or `<style>` element's contents are left out up to its end tag
+        let mut dropped_element: Option<String> = None;
+        for child in nodes {
+            if let NodeValue::HtmlInline(html_inline) = &child.data.borrow().value {
+                if let Some(name) = &dropped_element {
+                    if html::closes(html_inline, name) {
+                        dropped_element = None;
+                    }
+                    continue;
+                }
+                if context.options.html == HtmlMode::Strip {
+                    dropped_element = html::dropped_element(html_inline);
                 }
+            } else if dropped_element.is_some() {
+                continue;
+            }
+            let child_text = text_node_to_text(child, context);
+            // Without the image, the spaces on either side of it would run together
+            let child_text = if after_left_out_image && (text.is_empty() || text.ends_with(' ')) {
+                child_text.trim_start_matches(' ')
+            } else {
+                &child_text
+            };
+            text.push_str(child_text);
+            let left_out =
+                !context.options.images && matches!(child.data.borrow().value, NodeValue::Image(_));
+            after_left_out_image = left_out || (after_left_out_image && child_text.is_empty());
+        }
+        join_styled_runs(&text)
+    }
+    fn text_node_to_text<'a>(
+        text_node: &'a Node<'a, RefCell<Ast>>,
+        context: &RenderContext,
+    ) -> String {
+        match &text_node.data.borrow().value {
+            NodeValue::Emph => context.styled(context.options.theme.emphasis, || {
+                inline_children_to_text(text_node, context)
+            }),
+            NodeValue::Strong => context.styled(context.options.theme.strong, || {
+                inline_children_to_text(text_node, context)
+            }),
+            NodeValue::Underline => context.styled(context.options.theme.underline, || {
+                inline_children_to_text(text_node, context)
+            }),
+            NodeValue::Strikethrough => context.styled(context.options.theme.strikethrough, || {
+                inline_children_to_text(text_node, context)
+            }),
+            NodeValue::Superscript => {
+                let text = inline_children_to_text(text_node, &context.as_plain());
+                context.paint(&superscript::superscript(&text))
             }
             NodeValue::Code(code) => {
-                if plain {
-                    code.literal.to_string()
-                } else {
-                    Style::new()
-                        .fg(Color::White)
-                        .bold()
-                        .on(Color::Fixed(238))
-                        .paint(&code.literal)
-                        .to_string()
-                }
+                context.styled(context.options.theme.code, || context.paint(&code.literal))
+            }
+            NodeValue::Link(link) => link_node_to_text(text_node, link, context),
+            // Wiki links are shown like links to their page's name
+            NodeValue::WikiLink(wiki_link) => {
+                let link = NodeLink {
+                    url: wiki_link.url.clone(),
+                    title: String::new(),
+                };
+                link_node_to_text(text_node, &link, context)
             }
-            NodeValue::Link(image) | NodeValue::Image(image) => {
+            NodeValue::Image(_) if !context.options.images => String::default(),
+            NodeValue::Image(image) => {
                 let title = if !image.title.is_empty() {
                     format!(r#" "{}""#, image.title)
                 } else {
                     String::from("")
                 };
-                let text = node_children_to_text(text_node, plain);
-                let content = if plain {
-                    text.to_string()
-                } else {
-                    Style::new().underline().paint(text).to_string()
-                };
-                format!("{}{} [{}]", content, title, image.url)
+                let content = context.styled(context.options.theme.link, || {
+                    inline_children_to_text(text_node, context)
+                });
+                if context.options.text_only {
+                    return content;
+                }
+                join_styled_runs(&format!(
+                    "{}{}{}",
+                    context.paint(&context.options.image_marker),
+                    content,
+                    context.paint(&format!("{} [{}]", title, image.url))
+                ))
             }
-            NodeValue::Paragraph => paragraph_node_to_text(text_node, plain),
-            NodeValue::SoftBreak => String::from(" "),
-            NodeValue::LineBreak => String::from("\n"),
-            NodeValue::HtmlInline(html_inline) => html_inline.clone(),
-            NodeValue::Text(text) => text.clone(),
-            _ => {
-                eprintln!("💔 Unexpected child in Text node: {:#?}", text_node);
-                "💔 Unexpected child in Text node".to_string()
+            // Footnotes are numbered in the order they're first referenced
+            NodeValue::FootnoteReference(reference) => {
+                context.paint(&format!("[{}]", reference.ix))
             }
+            NodeValue::Paragraph => paragraph_node_to_text(text_node, context),
+            NodeValue::Math(math) if context.options.plain => math_source(math),
+            NodeValue::Math(math) => context.styled(context.options.theme.math, || {
+                context.paint(math.literal.trim())
+            }),
+            NodeValue::SoftBreak if context.options.preserve_breaks => String::from("\n"),
+            NodeValue::SoftBreak => context.paint(" "),
+            NodeValue::LineBreak => String::from("\n"),
+            NodeValue::HtmlInline(html_inline) => context.paint(&match context.options.html {
+                HtmlMode::Raw => html::decode_entities(html_inline),
+                HtmlMode::Strip => html::strip_tags(html_inline, ""),
+                HtmlMode::Escape => html::filter_tags(&html::decode_entities(html_inline)),
+            }),
+            // comrak has already decoded any entities in text, and decoding again would turn
+            // `&amp;lt;` into `<`
+            NodeValue::Text(text) => context.paint(text),
+            _ => context.unexpected_node(text_node, "Text"),
+        }
+    }
+    /// Link text followed by where the link goes, depending on the options
+    fn link_node_to_text<'a>(
+        text_node: &'a Node<'a, RefCell<Ast>>,
+        link: &NodeLink,
+        context: &RenderContext,
+    ) -> String {
+        let title = if !link.title.is_empty() {
+            format!(r#" "{}""#, link.title)
+        } else {
+            String::from("")
+        };
+        let hyperlink = context.options.hyperlinks && !context.options.plain;
+        let email = link.url.strip_prefix("mailto:");
+        let style = match email {
+            Some(_) => context.options.theme.email,
+            None => context.options.theme.link,
+        };
+        let content = context.styled(style, || inline_children_to_text(text_node, context));
+        let content = if hyperlink {
+            // OSC 8 makes the text itself a clickable link in terminals that support it
+            format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", link.url, content)
+        } else {
+            content
+        };
+        // A bare URL or email address that was turned into a link already shows where it goes
+        let text = plain_text(text_node);
+        let autolink = text == link.url || email == Some(text.as_str());
+        let suffix = if context.options.text_only {
+            return content;
+        } else if autolink || (hyperlink && !context.options.link_references) {
+            title
+        } else if context.options.link_references {
+            let number = context.link_reference(&link.url);
+            format!("[{}]{}", number, title)
+        } else {
+            format!("{} [{}]", title, link.url)
+        };
+        join_styled_runs(&format!("{}{}", content, context.paint(&suffix)))
+    }
+    fn thematic_break_node_to_text(context: &RenderContext) -> String {
+        if context.options.text_only {
+            return String::default();
+        }
+        match context.options.thematic_break {
+            // A short rule when there's no width to fill
+            Some(rule) => format!(
+                "{}\n",
+                rule.to_string().repeat(context.options.width.unwrap_or(3))
+            ),
+            None => String::from("¶\n"),
         }
     }
-    fn thematic_break_node_to_text() -> String {
-        String::from("¶\n")
+    /// If the blockquote starts with a GitHub alert marker line like `[!NOTE]`, returns the
+    /// alert's title, symbol and color, and the number of the first paragraph's inline nodes
+    /// that make up the marker line.
+    fn blockquote_alert<'a>(
+        blockquote_node: &'a Node<'a, RefCell<Ast>>,
+        theme: &Theme,
+    ) -> Option<(&'static str, &'static str, Style, usize)> {
+        let paragraph = blockquote_node.first_child()?;
+        if !matches!(paragraph.data.borrow().value, NodeValue::Paragraph) {
+            return None;
+        }
+        let mut marker = String::default();
+        let mut marker_len = 0;
+        for child in paragraph.children() {
+            marker_len += 1;
+            match &child.data.borrow().value {
+                NodeValue::Text(text) => marker.push_str(text),
+                NodeValue::SoftBreak | NodeValue::LineBreak => break,
+                _ => return None,
+            }
+        }
+        let (title, symbol, style) = match marker.trim().to_uppercase().as_str() {
+            "[!NOTE]" => ("NOTE", "ⓘ", theme.alert_note),
+            "[!TIP]" => ("TIP", "💡", theme.alert_tip),
+            "[!IMPORTANT]" => ("IMPORTANT", "❗", theme.alert_important),
+            "[!WARNING]" => ("WARNING", "⚠", theme.alert_warning),
+            "[!CAUTION]" => ("CAUTION", "⛔", theme.alert_caution),
+            _ => return None,
+        };
+        Some((title, symbol, style, marker_len))
     }
     fn blockquote_node_to_text<'a>(
         blockquote_node: &'a Node<'a, RefCell<Ast>>,
         level: usize,
-        plain: bool,
+        context: &RenderContext,
     ) -> String {
-        let blockquote = blockquote_node
-            .children()
-            .map(|child| match child.data.borrow().value {
-                NodeValue::BlockQuote => blockquote_node_to_text(child, level + 1, plain),
-                _ => {
-                    let lead = "│ ".repeat(level + 1);
-                    format!("{}{}\n", lead, node_children_to_text(child, plain))
+        let theme = &context.options.theme;
+        let levels = if context.options.text_only {
+            0..0
+        } else {
+            0..level + 1
+        };
+        let lead: String = levels
+            .map(|level| {
+                let lead = format!("{} ", theme.blockquote_lead);
+                match theme.blockquote_leads.len() {
+                    0 => lead,
+                    _ if context.options.plain => lead,
+                    len => theme.blockquote_leads[level % len].paint(lead).to_string(),
                 }
             })
             .collect();
+        let alert = blockquote_alert(blockquote_node, &context.options.theme);
+        let header = match alert {
+            Some((title, symbol, style, _)) => {
+                let header = format!("{} {}", symbol, title);
+                if context.options.plain {
+                    format!("{}{}\n", lead, header)
+                } else {
+                    format!("{}{}\n", lead, style.paint(header))
+                }
+            }
+            None => String::default(),
+        };
+        let mut blockquote = String::default();
+        for (index, child) in blockquote_node.children().enumerate() {
+            // Blocks are set apart with a line of just the leads, the way blank lines separate
+            // them outside of a blockquote. An alert marker alone on its line leaves nothing to
+            // set apart.
+            if !blockquote.is_empty() && !context.options.compact {
+                blockquote.push_str(&format!("{}\n", lead.trim_end()));
+            }
+            blockquote.push_str(&match child.data.borrow().value {
+                NodeValue::BlockQuote => blockquote_node_to_text(child, level + 1, context),
+                // Leave the alert marker line out of the body
+                _ if index == 0 && alert.is_some() => {
+                    let marker_len = alert.map_or(0, |(_, _, _, marker_len)| marker_len);
+                    let text = expand_tabs(
+                        &inline_nodes_to_text(child.children().skip(marker_len), context),
+                        context.options.tab_width,
+                    );
+                    // A marker with nothing after it leaves just the title
+                    if text.trim().is_empty() {
+                        String::default()
+                    } else {
+                        context
+                            .wrap_lines(&text, 2 * (level + 1))
+                            .iter()
+                            .map(|line| format!("{}{}\n", lead, line))
+                            .collect()
+                    }
+                }
+                _ => {
+                    let block = context.nested(2 * (level + 1), |context| {
+                        block_node_to_text(child, 0, "Block Quote", context)
+                    });
+                    let block = block.trim_end_matches('\n');
+                    if block.is_empty() {
+                        format!("{}\n", lead)
+                    } else {
+                        block
+                            .lines()
+                            .map(|line| match line {
+                                "" => format!("{}\n", lead.trim_end()),
+                                _ => format!("{}{}\n", lead, line),
+                            })
+                            .collect()
+                    }
+                }
+            });
+        }
         match level {
-            0 => format!("{}\n", blockquote),
-            _ => blockquote,
+            0 => format!(
+                "{}{}{}",
+                header,
+                blockquote.trim_end_matches('\n'),
+                context.block_end()
+            ),
+            _ => format!("{}{}", header, blockquote),
         }
     }
-    fn code_block_node_to_text(code_block: &mut NodeCodeBlock, plain: bool) -> String {
+    fn code_block_node_to_text(code_block: &NodeCodeBlock, context: &RenderContext) -> String {
+        if context.options.text_only {
+            let literal = expand_tabs(&code_block.literal, context.options.tab_width);
+            return format!("{}\n", literal);
+        }
         let info = if code_block.info.is_empty() {
             String::default()
         } else {
-            let info = format!("[{}]\n", code_block.info);
-            if plain {
-                info
+            let info = format!("[{}]", code_block.info);
+            // The newline is left out of the style so it doesn't carry onto the next line
+            if context.options.plain {
+                format!("{}\n", info)
             } else {
-                Style::new().reverse().paint(info).to_string()
+                format!("{}\n", context.options.theme.code_block_info.paint(info))
             }
         };
-        let lines: Vec<String> = code_block
-            .literal
-            .lines()
-            .map(|line| {
-                if plain {
-                    format!("║ {}", line)
+        let literal = expand_tabs(&code_block.literal, context.options.tab_width);
+        let highlighted = if context.options.plain || !context.options.highlight {
+            None
+        } else {
+            highlight::highlight_lines(
+                &code_block.info,
+                &literal,
+                &context.options.theme,
+                context.options.truecolor,
+            )
+        };
+        let lines: Vec<String> = match highlighted {
+            Some(highlighted) => highlighted
+                .iter()
+                .map(|line| {
+                    let background = Style {
+                        background: context.options.theme.code_block.background,
+                        ..Style::new()
+                    };
+                    background
+                        .paint(format!("{}{}", line, ansi_escapes::EraseEndLine))
+                        .to_string()
+                })
+                .collect(),
+            None => literal
+                .lines()
+                .map(|line| {
+                    if context.options.plain {
+                        line.to_string()
+                    } else {
+                        let fancy_line = context.options.theme.code_block.paint(format!(
+                            "{}{}",
+                            line,
+                            ansi_escapes::EraseEndLine
+                        ));
+                        fancy_line.to_string()
+                    }
+                })
+                .collect(),
+        };
+        let number_width = lines.len().to_string().len();
+        let lines: Vec<String> = lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let gutter = if context.options.code_line_numbers {
+                    format!("{:>1$} │ ", index + 1, number_width)
                 } else {
-                    let fancy_line = Style::new()
-                        .fg(Color::White)
-                        .bold()
-                        .on(Color::Fixed(238))
-                        .paint(format!("{}{}", line, ansi_escapes::EraseEndLine));
-                    fancy_line.to_string()
+                    String::default()
+                };
+                if context.options.plain && context.options.code_block_boxed {
+                    format!("{}{}", gutter, line)
+                } else if context.options.plain {
+                    format!("║ {}{}", gutter, line)
+                } else if gutter.is_empty() {
+                    line.to_string()
+                } else {
+                    format!(
+                        "{}{}",
+                        context.options.theme.line_number.paint(gutter),
+                        line
+                    )
                 }
             })
             .collect();
-        format!("{}{}\n\n", info, lines.join("\n"))
+        if context.options.plain && context.options.code_block_boxed {
+            let code_box = code_box(&lines, context.options.width);
+            let code_box = code_box.strip_suffix('\n').unwrap_or(&code_box);
+            return format!("{}{}{}", info, code_box, context.block_end());
+        }
+        format!("{}{}{}", info, lines.join("\n"), context.block_end())
     }
-    fn html_block_node_to_text(html_block_node: &mut NodeHtmlBlock, _plain: bool) -> String {
-        // We don't try to parse HTML
-        format!("{}\n", html_block_node.literal)
+    /// Draws a double-line box around the lines, padding them to the longest line or, when
+    /// wrapping, to the full width
+    fn code_box(lines: &[String], width: Option<usize>) -> String {
+        let longest = lines
+            .iter()
+            .map(|line| visible_width(line))
+            .max()
+            .unwrap_or(0);
+        let inner = width.map_or(longest, |width| width.saturating_sub(4).max(longest));
+        let mut text = format!("╔{}╗\n", "═".repeat(inner + 2));
+        for line in lines {
+            text.push_str(&format!(
+                "║ {}{} ║\n",
+                line,
+                " ".repeat(inner - visible_width(line))
+            ));
+        }
+        text.push_str(&format!("╚{}╝\n", "═".repeat(inner + 2)));
+        text
+    }
+    fn html_block_node_to_text(html_block_node: &NodeHtmlBlock, context: &RenderContext) -> String {
+        match context.options.html {
+            HtmlMode::Raw => format!("{}\n", html_block_node.literal),
+            HtmlMode::Escape => format!("{}\n", html::filter_tags(&html_block_node.literal)),
+            HtmlMode::Strip if html::details(&html_block_node.literal).is_some() => {
+                details_to_text(&html_block_node.literal, context)
+            }
+            HtmlMode::Strip => {
+                let text = html::strip_tags(
+                    &html_block_node.literal,
+                    &thematic_break_node_to_text(context),
+                );
+                let lines: Vec<&str> = text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                if lines.is_empty() {
+                    String::default()
+                } else {
+                    format!("{}{}", lines.join("\n"), context.block_end())
+                }
+            }
+        }
+    }
+    /// Renders any block that can appear inside a document or another block, indenting it by
+    /// `indent` columns and wrapping it to the width that's left. Headings aren't numbered, as
+    /// only top-level headings are.
+    fn block_node_to_text<'a>(
+        node: &'a Node<'a, RefCell<Ast>>,
+        indent: usize,
+        parent: &'static str,
+        context: &RenderContext,
+    ) -> String {
+        if indent > 0 {
+            let block = context.nested(indent, |context| {
+                block_node_to_text(node, 0, parent, context)
+            });
+            return indent_lines(&block, indent);
+        }
+        match &node.data.borrow().value {
+            NodeValue::Paragraph => paragraph_node_to_text(node, context),
+            NodeValue::List(_node_list) => list_node_to_text(node, 0, context),
+            NodeValue::Heading(heading) => heading_node_to_text(node, heading, None, context),
+            NodeValue::CodeBlock(code_block) => code_block_node_to_text(code_block, context),
+            NodeValue::ThematicBreak => thematic_break_node_to_text(context),
+            NodeValue::BlockQuote => blockquote_node_to_text(node, 0, context),
+            NodeValue::HtmlBlock(html_block) => html_block_node_to_text(html_block, context),
+            NodeValue::Table(node_table) => table_node_to_text(node, node_table, context),
+            NodeValue::DescriptionList => description_list_node_to_text(node, context),
+            _ => context.unexpected_node(node, parent),
+        }
+    }
+    /// Terminals can't collapse a `<details>` element, so it's shown expanded: the summary,
+    /// then the Markdown inside it, indented. Blocks separated from the `<details>` tag by a
+    /// blank line are parsed on their own, and the top-level loop indents those.
+    fn details_to_text(html: &str, context: &RenderContext) -> String {
+        let Some((summary, inner)) = html::details(html) else {
+            return String::default();
+        };
+        let summary = format!("▸ {}", summary);
+        let mut text = if context.options.plain {
+            format!("{}\n", summary)
+        } else {
+            format!("{}\n", context.options.theme.strong.paint(summary))
+        };
+        // With nothing else in the block, the summary runs straight into what comes next
+        if inner.trim().is_empty() {
+            return text;
+        }
+        // Front matter only comes at the start of the document
+        let mut options = comrak_options();
+        options.extension.front_matter_delimiter = None;
+        let arena = Arena::new();
+        let root = comrak::parse_document(&arena, inner, &options);
+        for child in root.children() {
+            text.push_str(&block_node_to_text(child, 2, "Details", context));
+        }
+        text
+    }
+    /// The front matter's title, rendered like a level 1 heading of that text
+    fn front_matter_title_to_text(title: String, context: &RenderContext) -> String {
+        let heading = NodeHeading {
+            level: 1,
+            setext: false,
+        };
+        let arena = Arena::new();
+        let start = LineColumn { line: 1, column: 1 };
+        let node = arena.alloc(Node::new(RefCell::new(Ast::new(
+            NodeValue::Heading(heading),
+            start,
+        ))));
+        node.append(arena.alloc(Node::new(RefCell::new(Ast::new(
+            NodeValue::Text(title),
+            start,
+        )))));
+        heading_node_to_text(node, &heading, None, context)
     }
     fn paragraph_node_to_text<'a>(
         paragraph_node: &'a Node<'a, RefCell<Ast>>,
-        plain: bool,
+        context: &RenderContext,
     ) -> String {
-        let paragraph = node_children_to_text(paragraph_node, plain);
-        format!("{}\n\n", paragraph)
+        if let Some(display_math) = display_math(paragraph_node) {
+            return display_math_to_text(&display_math, context);
+        }
+        let paragraph = node_children_to_text(paragraph_node, context);
+        let paragraph = match context.options.width {
+            Some(width) => wrap_styled_text(&paragraph, width).join("\n"),
+            None => paragraph,
+        };
+        format!("{}{}", paragraph, context.block_end())
+    }
+    /// The math if it's all there is in the paragraph and it's display math
+    fn display_math<'a>(paragraph_node: &'a Node<'a, RefCell<Ast>>) -> Option<NodeMath> {
+        let child = paragraph_node.first_child()?;
+        if child.next_sibling().is_some() {
+            return None;
+        }
+        match &child.data.borrow().value {
+            NodeValue::Math(math) if math.display_math => Some(math.clone()),
+            _ => None,
+        }
+    }
+    /// Terminals can't typeset TeX, so display math is shown as its source, set off by
+    /// indenting it
+    fn display_math_to_text(math: &NodeMath, context: &RenderContext) -> String {
+        let source = if context.options.plain {
+            math_source(math)
+        } else {
+            math.literal.trim().to_string()
+        };
+        let lines: Vec<String> = source
+            .trim()
+            .lines()
+            .map(|line| {
+                if context.options.plain {
+                    format!("    {}", line)
+                } else {
+                    format!("    {}", context.options.theme.math.paint(line))
+                }
+            })
+            .collect();
+        format!("{}{}", lines.join("\n"), context.block_end())
     }
     fn heading_node_to_text<'a>(
         node: &'a Node<'a, RefCell<Ast>>,
-        heading: &mut NodeHeading,
-        plain: bool,
+        heading: &NodeHeading,
+        section_number: Option<&str>,
+        context: &RenderContext,
     ) -> String {
-        let text = node_children_to_text(node, plain);
-        let heading_text = if plain {
-            ANSIString::from(&text)
+        let level = usize::from(heading.level).clamp(1, 6);
+        let heading_text = context.styled(context.options.theme.headings[level - 1], || {
+            let text = node_children_to_text(node, context);
+            match section_number {
+                Some(section_number) => join_styled_runs(&format!(
+                    "{}{}",
+                    context.paint(&format!("{} ", section_number)),
+                    text
+                )),
+                None => text,
+            }
+        });
+        let marker = if !context.options.heading_markers {
+            String::default()
+        } else if context.options.plain {
+            format!("{} ", "#".repeat(heading.level.into()))
         } else {
-            match heading.level {
-                1 => Style::new().bold().underline().paint(&text),
-                2 => Style::new().bold().italic().paint(&text),
-                3 => Style::new().italic().underline().paint(&text),
-                4 => Style::new().underline().paint(&text),
-                _ => Style::new().italic().paint(&text),
+            let marker = "#".repeat(heading.level.into());
+            format!("{} ", context.options.theme.heading_marker.paint(marker))
+        };
+        let line = format!("{}{}", marker, heading_text);
+        let padding = match context.options.width {
+            Some(width) if context.options.center_headings => {
+                width.saturating_sub(visible_width(&line)) / 2
             }
+            _ => 0,
+        };
+        let padding = " ".repeat(padding);
+        let underline = match heading.level {
+            1 => "=",
+            2 => "-",
+            _ => "",
         };
-        format!("{}\n\n", heading_text)
+        if context.options.setext_headings && !underline.is_empty() {
+            let underline = underline.repeat(visible_width(&line));
+            let end = context.block_end();
+            format!("{}{}\n{}{}{}", padding, line, padding, underline, end)
+        } else {
+            format!("{}{}{}", padding, line, context.block_end())
+        }
     }
     fn table_node_to_text<'a>(
         table_node: &'a Node<'a, RefCell<Ast>>,
-        node_table: &mut NodeTable,
-        plain: bool,
+        node_table: &NodeTable,
+        context: &RenderContext,
     ) -> String {
         fn max_column_widths<'a>(
             table_node: &'a Node<'a, RefCell<Ast>>,
-            node_table: &mut NodeTable,
+            node_table: &NodeTable,
+            context: &RenderContext,
         ) -> Vec<usize> {
+            // Measure the cells rendered the same way they'll be displayed, since hyperlinks,
+            // for one, only leave out the URL when styled
+            let measuring_context = context.for_measuring();
             let column_widths: Vec<Vec<usize>> = table_node
                 .children()
                 .map(|row| match row.data.borrow().value {
                     NodeValue::TableRow(_is_header) => row
                         .children()
                         .map(|cell| match cell.data.borrow().value {
-                            NodeValue::TableCell => node_children_to_text(cell, true).len(),
+                            NodeValue::TableCell => {
+                                visible_width(&node_children_to_text(cell, &measuring_context))
+                            }
                             _ => 0,
                         })
                         .collect(),
@@ -202,192 +1508,552 @@ fn ast_to_text<'a>(root: &'a Node<'a, RefCell<Ast>>, plain: bool) -> String {
                 column_widths
                     .iter()
                     .fold(vec![0; node_table.num_columns], |mut acc, row| {
-                        for i in 0..node_table.num_columns {
-                            if row[i] > acc[i] {
-                                acc[i] = row[i];
+                        for (i, max_width) in acc.iter_mut().enumerate() {
+                            let width = row.get(i).copied().unwrap_or(0);
+                            if width > *max_width {
+                                *max_width = width;
                             }
                         }
                         acc
                     });
-            max_column_widths
+            let mut column_widths: Vec<usize> = match context.options.table_max_column_width {
+                Some(max_width) => max_column_widths
+                    .into_iter()
+                    .map(|width| width.min(max_width))
+                    .collect(),
+                None => max_column_widths,
+            };
+            // Narrow the widest column a column at a time until the table fits the width
+            if let Some(width) = context.options.width {
+                let gaps = if context.options.table_borders {
+                    3 * column_widths.len() + 1
+                } else {
+                    column_widths.len()
+                };
+                let mut table_width: usize = column_widths.iter().sum::<usize>() + gaps;
+                while table_width > width {
+                    match column_widths.iter_mut().max() {
+                        Some(widest) if *widest > 1 => *widest -= 1,
+                        _ => break,
+                    }
+                    table_width -= 1;
+                }
+            }
+            column_widths
+        }
+        fn table_border_to_text(
+            column_widths: &[usize],
+            left: char,
+            middle: char,
+            right: char,
+        ) -> String {
+            let segments: Vec<String> = column_widths
+                .iter()
+                .map(|width| "─".repeat(width + 2))
+                .collect();
+            format!("{}{}{}\n", left, segments.join(&middle.to_string()), right)
+        }
+        fn table_header_separator_to_text(
+            column_widths: &[usize],
+            alignments: &[TableAlignment],
+        ) -> String {
+            let segments: Vec<String> = column_widths
+                .iter()
+                .enumerate()
+                .map(|(index, width)| {
+                    let mut segment = vec!['-'; *width];
+                    let alignment = alignments.get(index).copied();
+                    if let Some(TableAlignment::Left | TableAlignment::Center) = alignment {
+                        if let Some(first) = segment.first_mut() {
+                            *first = ':';
+                        }
+                    }
+                    if let Some(TableAlignment::Right | TableAlignment::Center) = alignment {
+                        if let Some(last) = segment.last_mut() {
+                            *last = ':';
+                        }
+                    }
+                    segment.into_iter().collect()
+                })
+                .collect();
+            format!("{} \n", segments.join(" "))
         }
         fn table_cell_node_to_text<'a>(
             table_cell_node: &'a Node<'a, RefCell<Ast>>,
             is_header: bool,
             width: usize,
             alignment: TableAlignment,
-            plain: bool,
-        ) -> String {
-            let plain_content = node_children_to_text(table_cell_node, true);
-            let padding = width - plain_content.len();
-            let (padding_left, padding_right) = match alignment {
-                TableAlignment::Center => {
-                    let left_padding = padding / 2;
-                    let right_padding = padding - left_padding;
-                    (" ".repeat(left_padding), " ".repeat(right_padding))
-                }
-                TableAlignment::Right => (" ".repeat(padding), String::default()),
-                TableAlignment::None | TableAlignment::Left => {
-                    (String::default(), " ".repeat(padding))
-                }
+            context: &RenderContext,
+        ) -> Vec<String> {
+            let content = if is_header {
+                context.styled(context.options.theme.table_header, || {
+                    node_children_to_text(table_cell_node, context)
+                })
+            } else {
+                node_children_to_text(table_cell_node, context)
             };
-            let text = node_children_to_text(table_cell_node, plain);
-            let content = if is_header && !plain {
-                Style::new().bold().underline().paint(&text)
+            let lines = if visible_width(&content) > width {
+                wrap_styled_text(&content, width)
             } else {
-                ANSIString::from(text)
+                vec![content]
             };
-            format!("{}{} {}", padding_left, content, padding_right)
+            lines
+                .iter()
+                .map(|line| {
+                    let padding = width.saturating_sub(visible_width(line));
+                    let (padding_left, padding_right) = match alignment {
+                        TableAlignment::Center => {
+                            let left_padding = padding / 2;
+                            let right_padding = padding - left_padding;
+                            (" ".repeat(left_padding), " ".repeat(right_padding))
+                        }
+                        TableAlignment::Right => (" ".repeat(padding), String::default()),
+                        TableAlignment::None | TableAlignment::Left => {
+                            (String::default(), " ".repeat(padding))
+                        }
+                    };
+                    format!("{}{}{}", padding_left, line, padding_right)
+                })
+                .collect()
         }
         fn table_row_node_to_text<'a>(
             table_row_node: &'a Node<'a, RefCell<Ast>>,
             is_header: bool,
+            striped: bool,
             column_widths: &[usize],
             alignments: &[TableAlignment],
-            plain: bool,
+            context: &RenderContext,
         ) -> String {
-            let row: Vec<String> = table_row_node
-                .children()
+            let cells: Vec<&'a Node<'a, RefCell<Ast>>> = table_row_node.children().collect();
+            let row: Vec<Vec<String>> = column_widths
+                .iter()
                 .enumerate()
-                .map(|(index, child)| match child.data.borrow().value {
-                    NodeValue::TableCell => table_cell_node_to_text(
-                        child,
-                        is_header,
-                        column_widths[index],
-                        alignments[index],
-                        plain,
-                    ),
-                    _ => {
-                        eprintln!("💔 Unexpected child in Table Row node: {:#?}", child);
-                        "💔 Unexpected child in Table Row node".to_string()
-                    }
+                .map(|(index, width)| match cells.get(index) {
+                    Some(child) => match child.data.borrow().value {
+                        NodeValue::TableCell => table_cell_node_to_text(
+                            child,
+                            is_header,
+                            *width,
+                            alignments
+                                .get(index)
+                                .copied()
+                                .unwrap_or(TableAlignment::None),
+                            context,
+                        ),
+                        _ => {
+                            vec![context.unexpected_node(child, "Table Row")]
+                        }
+                    },
+                    // Missing cells in a ragged row render as blank padding
+                    None => vec![],
                 })
                 .collect();
-            format!("{}\n", row.join(""))
+            // Cells shorter than the tallest cell in the row are padded with blank lines
+            let height = row.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            (0..height)
+                .map(|line_index| {
+                    let line: Vec<String> = row
+                        .iter()
+                        .zip(column_widths)
+                        .map(|(cell, width)| match cell.get(line_index) {
+                            Some(cell_line) => cell_line.clone(),
+                            None => " ".repeat(*width),
+                        })
+                        .collect();
+                    let line = if context.options.table_borders {
+                        format!("│ {} │", line.join(" │ "))
+                    } else {
+                        format!("{} ", line.join(" "))
+                    };
+                    let stripe = context.options.theme.table_stripe;
+                    if striped && !context.options.plain {
+                        // The stripe runs unbroken across the row, around the cells' styles
+                        format!("{}\n", paint_nested(stripe, &line))
+                    } else {
+                        format!("{}\n", line)
+                    }
+                })
+                .collect()
+        }
+        /// The columns' alignments, with numeric columns right-aligned if the options say so
+        fn column_alignments<'a>(
+            table_node: &'a Node<'a, RefCell<Ast>>,
+            node_table: &NodeTable,
+            context: &RenderContext,
+        ) -> Vec<TableAlignment> {
+            let mut alignments = node_table.alignments.clone();
+            if !context.options.table_align_numbers {
+                return alignments;
+            }
+            for (column, alignment) in alignments.iter_mut().enumerate() {
+                if *alignment != TableAlignment::None {
+                    continue;
+                }
+                let cells: Vec<String> = table_node
+                    .children()
+                    .filter(|row| matches!(row.data.borrow().value, NodeValue::TableRow(false)))
+                    .filter_map(|row| row.children().nth(column))
+                    .map(|cell| plain_text(cell).trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .collect();
+                if !cells.is_empty() && cells.iter().all(|text| is_number(text)) {
+                    *alignment = TableAlignment::Right;
+                }
+            }
+            alignments
         }
-        let max_column_widths = max_column_widths(table_node, node_table);
-        let table: Vec<String> = table_node
+        let max_column_widths = max_column_widths(table_node, node_table, context);
+        let alignments = column_alignments(table_node, node_table, context);
+        let mut table: Vec<String> = vec![];
+        if context.options.table_borders {
+            table.push(table_border_to_text(&max_column_widths, '┌', '┬', '┐'));
+        }
+        table_node
             .children()
-            .map(|child| match child.data.borrow().value {
-                NodeValue::TableRow(is_header) => table_row_node_to_text(
-                    child,
-                    is_header,
-                    &max_column_widths,
-                    &node_table.alignments,
-                    plain,
-                ),
+            .enumerate()
+            .for_each(|(index, child)| match child.data.borrow().value {
+                NodeValue::TableRow(is_header) => {
+                    table.push(table_row_node_to_text(
+                        child,
+                        is_header,
+                        // The header is row 0, so this is every other body row from the second
+                        !is_header && index % 2 == 0,
+                        &max_column_widths,
+                        &alignments,
+                        context,
+                    ));
+                    if is_header {
+                        table.push(if context.options.table_borders {
+                            table_border_to_text(&max_column_widths, '├', '┼', '┤')
+                        } else {
+                            table_header_separator_to_text(&max_column_widths, &alignments)
+                        });
+                    }
+                }
                 _ => {
-                    eprintln!("💔 Unexpected child in Table node: {:#?}", child);
-                    "💔 Unexpected child in Table node".to_string()
+                    table.push(context.unexpected_node(child, "Table"));
+                }
+            });
+        if context.options.table_borders {
+            table.push(table_border_to_text(&max_column_widths, '└', '┴', '┘'));
+        }
+        let table = table.join("");
+        format!("{}{}", table.trim_end_matches('\n'), context.block_end())
+    }
+    fn description_list_node_to_text<'a>(
+        description_list_node: &'a Node<'a, RefCell<Ast>>,
+        context: &RenderContext,
+    ) -> String {
+        description_list_node
+            .children()
+            .map(|item| match item.data.borrow().value {
+                NodeValue::DescriptionItem(_) => {
+                    let text: String = item
+                        .children()
+                        .map(|child| match child.data.borrow().value {
+                            NodeValue::DescriptionTerm => {
+                                context.styled(context.options.theme.strong, || {
+                                    marked_blocks_to_text(
+                                        child,
+                                        "",
+                                        false,
+                                        "Description Term",
+                                        context,
+                                    )
+                                })
+                            }
+                            // The definition's continuation lines line up under its text
+                            NodeValue::DescriptionDetails => marked_blocks_to_text(
+                                child,
+                                "  : ",
+                                false,
+                                "Description Details",
+                                context,
+                            ),
+                            _ => context.unexpected_node(child, "Description Item"),
+                        })
+                        .collect();
+                    format!("{}{}", text.trim_end_matches('\n'), context.block_end())
                 }
+                _ => context.unexpected_node(item, "Description List"),
             })
-            .collect();
-        format!("{}\n", table.join(""))
+            .collect()
+    }
+    fn section_heading_to_text(heading: &str, context: &RenderContext) -> String {
+        if context.options.plain {
+            format!("{}{}", heading, context.block_end())
+        } else {
+            let heading = context.options.theme.section_heading.paint(heading);
+            format!("{}{}", heading, context.block_end())
+        }
+    }
+    fn footnote_definition_node_to_text<'a>(
+        footnote_definition_node: &'a Node<'a, RefCell<Ast>>,
+        number: usize,
+        context: &RenderContext,
+    ) -> String {
+        let marker = format!("[{}] ", number);
+        let separated = !context.options.compact;
+        marked_blocks_to_text(
+            footnote_definition_node,
+            &marker,
+            separated,
+            "Footnote Definition",
+            context,
+        )
+    }
+    /// Renders the blocks inside `node` lined up with the text after `marker`, which is put
+    /// in front of the first line. With `separated`, the blocks have blank lines between them.
+    fn marked_blocks_to_text<'a>(
+        node: &'a Node<'a, RefCell<Ast>>,
+        marker: &str,
+        separated: bool,
+        parent: &'static str,
+        context: &RenderContext,
+    ) -> String {
+        let content_indent = " ".repeat(visible_width(marker));
+        let mut text = String::default();
+        for (index, child) in node.children().enumerate() {
+            if index > 0 && separated {
+                text.push('\n');
+            }
+            let block = block_node_to_text(child, content_indent.len(), parent, context);
+            for line in block.trim_end_matches('\n').lines() {
+                match line.strip_prefix(&content_indent) {
+                    Some(line) if index == 0 && text.is_empty() => {
+                        text.push_str(&format!("{}{}\n", marker, line));
+                    }
+                    _ => text.push_str(&format!("{}\n", line)),
+                }
+            }
+        }
+        if text.is_empty() {
+            text = format!("{}\n", marker.trim_end());
+        }
+        text
     }
     fn list_node_to_text<'a>(
         list_node: &'a Node<'a, RefCell<Ast>>,
         level: usize,
-        plain: bool,
+        context: &RenderContext,
     ) -> String {
         fn item_node_to_text<'a>(
             item_node: &'a Node<'a, RefCell<Ast>>,
             index: usize,
             level: usize,
             node_list: &NodeList,
-            plain: bool,
+            number_width: usize,
+            task: Option<bool>,
+            context: &RenderContext,
         ) -> String {
-            item_node
-                .children()
-                .map(|child| match child.data.borrow().value {
-                    NodeValue::List(_node_list) => list_node_to_text(child, level + 1, plain),
-                    NodeValue::Paragraph => {
-                        let (marker, marker_len) = if node_list.list_type == ListType::Bullet {
-                            (
-                                match level {
-                                    0 => "•",
-                                    1 => "◦",
-                                    _ => "▪",
-                                }
-                                .to_string(),
-                                1,
-                            )
-                        } else {
-                            let delimiter = match node_list.delimiter {
-                                ListDelimType::Period => '.',
-                                ListDelimType::Paren => ')',
-                            };
-                            (format!("{}{}", index + 1, delimiter), 2)
-                        };
-                        let indent = " ".repeat(level * 4);
-                        let marker_space = " ".repeat(marker_len);
-                        node_children_to_text(child, plain)
-                            .lines()
-                            .enumerate()
-                            .map(|(index, line)| match index {
-                                0 => format!("{}{} {}\n", indent, marker, line),
-                                _ => format!("{}{} {}\n", indent, marker_space, line),
-                            })
-                            .collect()
-                    }
-                    _ => {
-                        eprintln!("💔 Unexpected child in List Item node: {:#?}", child);
-                        "💔 Unexpected child in List Item node".to_string()
+            let (marker, marker_len) = if let Some(checked) = task {
+                match (checked, context.options.plain) {
+                    (true, true) => (String::from("[x]"), 3),
+                    (false, true) => (String::from("[ ]"), 3),
+                    (true, false) => (context.options.theme.task_checked.paint("☑").to_string(), 1),
+                    (false, false) => (String::from("☐"), 1),
+                }
+            } else if node_list.list_type == ListType::Bullet {
+                let bullets = &context.options.bullets;
+                let bullet = match bullets.len() {
+                    0 => "•",
+                    len => &bullets[level % len],
+                };
+                (bullet.to_string(), visible_width(bullet))
+            } else {
+                let delimiter = match node_list.delimiter {
+                    ListDelimType::Period => '.',
+                    ListDelimType::Paren => ')',
+                };
+                let number = node_list.start + index;
+                let marker = format!("{:>1$}{2}", number, number_width, delimiter);
+                (marker, number_width + 1)
+            };
+            let indent = " ".repeat(level * context.options.list_indent);
+            let marker = format!("{}{} ", indent, marker);
+            // The item's blocks line up with the text after its marker
+            let content_indent = " ".repeat(indent.len() + marker_len + 1);
+            let mut text = String::default();
+            for (index, child) in item_node.children().enumerate() {
+                if let NodeValue::List(_node_list) = child.data.borrow().value {
+                    text.push_str(&list_node_to_text(child, level + 1, context));
+                    continue;
+                }
+                // The blocks of a loose list's items are separated by blank lines
+                if index > 0 && !node_list.tight && !context.options.compact {
+                    text.push('\n');
+                }
+                let block = block_node_to_text(child, content_indent.len(), "List Item", context);
+                for line in block.trim_end_matches('\n').lines() {
+                    match line.strip_prefix(&content_indent) {
+                        Some(line) if index == 0 && text.is_empty() => {
+                            text.push_str(&format!("{}{}\n", marker, line));
+                        }
+                        _ => text.push_str(&format!("{}\n", line)),
                     }
-                })
-                .collect()
+                }
+            }
+            if text.is_empty() {
+                text = format!("{}\n", marker.trim_end());
+            }
+            text
         }
+        let list_node_list = match list_node.data.borrow().value {
+            NodeValue::List(node_list) => node_list,
+            _ => NodeList::default(),
+        };
+        // Numbers are right-aligned to the width of the list's last (widest) number
+        let item_count = list_node.children().count();
+        let number_width = (list_node_list.start + item_count.saturating_sub(1))
+            .to_string()
+            .len();
         let items = list_node
             .children()
             .enumerate()
             .map(|(index, child)| match child.data.borrow().value {
-                NodeValue::Item(item_node_list) => {
-                    item_node_to_text(child, index, level, &item_node_list, plain)
-                }
-                _ => {
-                    eprintln!("💔 Unexpected child in List node: {:#?}", child);
-                    "💔 Unexpected child in List node".to_string()
-                }
+                // Items are numbered from the list's start, so use the list's details
+                // rather than the item's own
+                NodeValue::Item(_item_node_list) => item_node_to_text(
+                    child,
+                    index,
+                    level,
+                    &list_node_list,
+                    number_width,
+                    None,
+                    context,
+                ),
+                NodeValue::TaskItem(symbol) => item_node_to_text(
+                    child,
+                    index,
+                    level,
+                    &list_node_list,
+                    number_width,
+                    Some(symbol.is_some()),
+                    context,
+                ),
+                _ => context.unexpected_node(child, "List"),
             })
             .collect::<Vec<String>>()
             .join("");
+        let items = match task_progress(list_node, level, context) {
+            Some(progress) => format!("{}{}", progress, items),
+            None => items,
+        };
         if level == 0 {
-            format!("{}\n", items)
+            format!("{}{}", items.trim_end_matches('\n'), context.block_end())
         } else {
             items
         }
     }
-
-    let mut document: Vec<String> = vec![];
-    root.children()
-        .for_each(|child| match &mut child.data.borrow_mut().value {
-            NodeValue::Paragraph => {
-                document.push(paragraph_node_to_text(child, plain));
-            }
-            NodeValue::List(_node_list) => {
-                document.push(list_node_to_text(child, 0, plain));
-            }
-            NodeValue::Heading(heading) => {
-                document.push(heading_node_to_text(child, heading, plain));
-            }
-            NodeValue::CodeBlock(code_block) => {
-                document.push(code_block_node_to_text(code_block, plain));
-            }
-            NodeValue::ThematicBreak => {
-                document.push(thematic_break_node_to_text());
+    /// The progress line for a list whose items are all tasks, green once they're all done
+    fn task_progress<'a>(
+        list_node: &'a Node<'a, RefCell<Ast>>,
+        level: usize,
+        context: &RenderContext,
+    ) -> Option<String> {
+        if !context.options.task_progress || context.options.text_only {
+            return None;
+        }
+        let mut done = 0;
+        let mut total = 0;
+        for item in list_node.children() {
+            match item.data.borrow().value {
+                NodeValue::TaskItem(symbol) => {
+                    total += 1;
+                    done += usize::from(symbol.is_some());
+                }
+                _ => return None,
             }
-            NodeValue::BlockQuote => {
-                document.push(blockquote_node_to_text(child, 0, plain));
+        }
+        if total == 0 {
+            return None;
+        }
+        let progress = format!("({}/{} done)", done, total);
+        let progress = if done == total && !context.options.plain {
+            context
+                .options
+                .theme
+                .task_checked
+                .paint(progress)
+                .to_string()
+        } else {
+            progress
+        };
+        let indent = " ".repeat(level * context.options.list_indent);
+        Some(format!("{}{}\n", indent, progress))
+    }
+
+    let mut out = BlockWriter::new(out, &context.options);
+    // comrak moves the referenced footnote definitions to the end of the document, in the
+    // order they're first referenced, so they're collected and numbered in that order
+    let mut footnotes: Vec<String> = vec![];
+    // Whether a footnote has blank lines between its blocks, so the footnotes need them too
+    let mut footnotes_have_blocks = false;
+    // The count of headings seen at each level, from H1 down to the last heading's level
+    let mut section_counters: Vec<usize> = vec![];
+    // The number of `<details>` elements the blocks are in, each of which indents them
+    let mut details_depth: usize = 0;
+    for child in root.children() {
+        let indent = 2 * details_depth;
+        if let NodeValue::HtmlBlock(html_block) = &child.data.borrow().value {
+            if context.options.html == HtmlMode::Strip {
+                let depth = details_depth as isize + html::details_depth(&html_block.literal);
+                details_depth = depth.max(0) as usize;
             }
-            NodeValue::HtmlBlock(html_block) => {
-                document.push(html_block_node_to_text(html_block, plain));
+        }
+        let block = match &child.data.borrow().value {
+            // Front matter is data for other tools, like a static site generator
+            NodeValue::FrontMatter(front_matter) => {
+                let title = front_matter::title(front_matter)
+                    .filter(|_| context.options.title_from_frontmatter);
+                let Some(title) = title else {
+                    continue;
+                };
+                front_matter_title_to_text(title, context)
             }
-            NodeValue::Table(node_table) => {
-                document.push(table_node_to_text(child, node_table, plain));
+            NodeValue::FootnoteDefinition(_) => {
+                let number = footnotes.len() + 1;
+                footnotes_have_blocks |= child.children().nth(1).is_some();
+                footnotes.push(footnote_definition_node_to_text(child, number, context));
+                continue;
             }
-            _ => {
-                eprintln!("💔 Unexpected child in List Item node: {:#?}", child);
-                document.push("💔 Unexpected child in List Item node".to_string());
+            NodeValue::Heading(heading) => {
+                let section_number = context
+                    .options
+                    .number_headings
+                    .then(|| next_section_number(&mut section_counters, heading.level));
+                let heading = context.nested(indent, |context| {
+                    heading_node_to_text(child, heading, section_number.as_deref(), context)
+                });
+                indent_lines(&heading, indent)
             }
-        });
-    document.join("")
+            _ => block_node_to_text(child, indent, "Document", context),
+        };
+        let preformatted = matches!(
+            child.data.borrow().value,
+            NodeValue::CodeBlock(_) | NodeValue::HtmlBlock(_)
+        );
+        out.write_block(&block, preformatted)?;
+    }
+    if !footnotes.is_empty() {
+        let mut section = section_heading_to_text("Footnotes", context);
+        let separator = if footnotes_have_blocks && !context.options.compact {
+            "\n"
+        } else {
+            ""
+        };
+        section.push_str(&footnotes.join(separator));
+        let section = format!("{}{}", section.trim_end_matches('\n'), context.block_end());
+        out.write_block(&section, false)?;
+    }
+    let links = context.links.borrow();
+    if !links.is_empty() {
+        let mut section = section_heading_to_text("References", context);
+        for (index, url) in links.iter().enumerate() {
+            section.push_str(&format!("[{}] {}\n", index + 1, url));
+        }
+        let section = format!("{}{}", section.trim_end_matches('\n'), context.block_end());
+        out.write_block(&section, false)?;
+    }
+    Ok(())
 }
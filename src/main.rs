@@ -1,21 +1,652 @@
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+use config::Config;
+use prmd::{HtmlMode, RenderOptions, Renderer, Theme};
+use std::env;
 use std::fs;
-use std::path::PathBuf;
-use clap::Parser;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+
+mod config;
+mod remote;
 
 #[derive(Parser)]
 #[command(version, about)]
+#[command(
+    after_help = "Defaults for the options can be set in prmd/config.toml in \
+    $XDG_CONFIG_HOME, ~/.config or %APPDATA%, using the long option names as keys. Command \
+    line options take precedence over environment variables like NO_COLOR and COLUMNS, which \
+    take precedence over the config file. A flag that the config file turns on can be turned \
+    off with --no-<flag>, or with --highlight and --images for --no-highlight and \
+    --no-images."
+)]
 struct Args {
-    /// Print the file without ANSI formatting
+    /// Print the file without ANSI formatting; implied when stdout isn't a terminal, the
+    /// NO_COLOR environment variable is set to a non-empty value, or CLICOLOR is 0
     #[arg(short, long)]
     plain: bool,
-    /// The file to print
-    file: PathBuf,
+    /// Print ANSI formatting even when stdout isn't a terminal; implied when the
+    /// CLICOLOR_FORCE environment variable is set to anything but 0
+    #[arg(long, conflicts_with = "plain")]
+    force_color: bool,
+    /// Prefix headings with `#` markers showing their level
+    #[arg(long)]
+    heading_markers: bool,
+    /// Overrides --heading-markers
+    #[arg(long, hide = true, overrides_with = "heading_markers")]
+    no_heading_markers: bool,
+    /// Number headings like sections: 1, 1.1, 1.2, 2, ...
+    #[arg(long)]
+    number_headings: bool,
+    /// Overrides --number-headings
+    #[arg(long, hide = true, overrides_with = "number_headings")]
+    no_number_headings: bool,
+    /// Center headings within the width
+    #[arg(long)]
+    center_headings: bool,
+    /// Overrides --center-headings
+    #[arg(long, hide = true, overrides_with = "center_headings")]
+    no_center_headings: bool,
+    /// Underline level 1 and 2 headings with a row of `=` or `-`
+    #[arg(long)]
+    setext_headings: bool,
+    /// Overrides --setext-headings
+    #[arg(long, hide = true, overrides_with = "setext_headings")]
+    no_setext_headings: bool,
+    /// Show the title from the document's front matter as a heading
+    #[arg(long)]
+    title_from_frontmatter: bool,
+    /// Overrides --title-from-frontmatter
+    #[arg(long, hide = true, overrides_with = "title_from_frontmatter")]
+    no_title_from_frontmatter: bool,
+    /// Show emphasis and strong text in color, for terminals without italic or bold
+    #[arg(long, conflicts_with = "theme")]
+    color_emphasis: bool,
+    /// Overrides --color-emphasis
+    #[arg(long, hide = true, overrides_with = "color_emphasis")]
+    no_color_emphasis: bool,
+    /// The styles to use: "dark", "light", or the path of a TOML theme file
+    #[arg(long, value_name = "NAME|PATH")]
+    theme: Option<String>,
+    /// Draw borders around table cells
+    #[arg(short, long)]
+    borders: bool,
+    /// Overrides --borders
+    #[arg(long, hide = true, overrides_with = "borders")]
+    no_borders: bool,
+    /// Right-align table columns of numbers that don't have an alignment
+    #[arg(long)]
+    align_numbers: bool,
+    /// Overrides --align-numbers
+    #[arg(long, hide = true, overrides_with = "align_numbers")]
+    no_align_numbers: bool,
+    /// Don't syntax highlight code blocks
+    #[arg(long)]
+    no_highlight: bool,
+    /// Overrides --no-highlight
+    #[arg(long, hide = true, overrides_with = "no_highlight")]
+    highlight: bool,
+    /// Number the lines of code blocks
+    #[arg(short = 'n', long)]
+    line_numbers: bool,
+    /// Overrides --line-numbers
+    #[arg(long, hide = true, overrides_with = "line_numbers")]
+    no_line_numbers: bool,
+    /// Draw a box around code blocks when printing without ANSI formatting
+    #[arg(long)]
+    boxed_code: bool,
+    /// Overrides --boxed-code
+    #[arg(long, hide = true, overrides_with = "boxed_code")]
+    no_boxed_code: bool,
+    /// Leave images out
+    #[arg(long)]
+    no_images: bool,
+    /// Overrides --no-images
+    #[arg(long, hide = true, overrides_with = "no_images")]
+    images: bool,
+    /// Show how many items are done above each task list
+    #[arg(long)]
+    task_progress: bool,
+    /// Overrides --task-progress
+    #[arg(long, hide = true, overrides_with = "task_progress")]
+    no_task_progress: bool,
+    /// Make links clickable in terminals that support OSC 8 hyperlinks
+    #[arg(long)]
+    hyperlinks: bool,
+    /// Overrides --hyperlinks
+    #[arg(long, hide = true, overrides_with = "hyperlinks")]
+    no_hyperlinks: bool,
+    /// List link URLs in a References section at the end instead of inline
+    #[arg(short = 'r', long)]
+    references: bool,
+    /// Overrides --references
+    #[arg(long, hide = true, overrides_with = "references")]
+    no_references: bool,
+    /// Print HTML blocks as written instead of stripping their tags
+    #[arg(long)]
+    raw_html: bool,
+    /// Overrides --raw-html
+    #[arg(long, hide = true, overrides_with = "raw_html")]
+    no_raw_html: bool,
+    /// Print HTML blocks as written, but with tags like <script> and <iframe> escaped
+    #[arg(long, conflicts_with = "raw_html")]
+    escape_html: bool,
+    /// Overrides --escape-html
+    #[arg(long, hide = true, overrides_with = "escape_html")]
+    no_escape_html: bool,
+    /// Print a table of contents listing the headings before each document
+    #[arg(long)]
+    toc: bool,
+    /// Expand tabs to stops this many columns apart
+    #[arg(long, default_value_t = 4)]
+    tab_width: usize,
+    /// Wrap text to this many columns [default: $COLUMNS, the terminal width, or 80]
+    #[arg(short, long, visible_alias = "columns")]
+    width: Option<usize>,
+    /// Keep the line breaks within paragraphs instead of joining the lines
+    #[arg(long)]
+    preserve_breaks: bool,
+    /// Overrides --preserve-breaks
+    #[arg(long, hide = true, overrides_with = "preserve_breaks")]
+    no_preserve_breaks: bool,
+    /// Leave out the blank lines between blocks
+    #[arg(long)]
+    compact: bool,
+    /// Overrides --compact
+    #[arg(long, hide = true, overrides_with = "compact")]
+    no_compact: bool,
+    /// Markdown extensions to parse, separated by commas, or with `no-` in front to turn
+    /// off ones that are on by default: autolink, description-lists, footnotes,
+    /// front-matter, math, strikethrough, superscript, table, tasklist, underline and
+    /// wikilinks
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', value_parser = extension)]
+    extensions: Vec<(String, bool)>,
+    /// Indent the whole document by this many columns
+    #[arg(long, default_value_t = 0)]
+    margin: usize,
+    /// Write the output to this file instead of stdout, without ANSI formatting unless
+    /// --force-color is given
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Print the number of words and characters and the reading time instead of the text
+    #[arg(long)]
+    stats: bool,
+    /// Don't warn about Markdown that can't be rendered
+    #[arg(short, long, conflicts_with = "strict")]
+    quiet: bool,
+    /// Treat Markdown that can't be rendered as an error instead of printing a placeholder
+    #[arg(long)]
+    strict: bool,
+    /// Page the output through $PAGER, or `less -R`, when stdout is a terminal
+    #[arg(long)]
+    pager: bool,
+    /// Overrides --pager
+    #[arg(long, hide = true, overrides_with = "pager")]
+    no_pager: bool,
+    /// Print a completion script for this shell and exit
+    #[arg(long, value_name = "SHELL", hide = true)]
+    completions: Option<Shell>,
+    /// Print a man page in roff format and exit
+    #[arg(long, hide = true)]
+    manpage: bool,
+    /// The files to print; reads stdin if omitted or "-". With the remote feature, http and
+    /// https URLs are downloaded
+    files: Vec<PathBuf>,
+}
+
+impl Args {
+    /// Turns on the flags that the config file turns on and fills in the options it sets,
+    /// except where the command line already says otherwise. --plain and --width are left
+    /// alone since environment variables come between the two.
+    fn with_config(mut self, config: &Config) -> Args {
+        // A flag that the command line turns off with its counterpart stays off
+        let flag = |on: bool, off: bool, config: bool| on || (!off && config);
+        self.heading_markers = flag(
+            self.heading_markers,
+            self.no_heading_markers,
+            config.heading_markers,
+        );
+        self.number_headings = flag(
+            self.number_headings,
+            self.no_number_headings,
+            config.number_headings,
+        );
+        self.center_headings = flag(
+            self.center_headings,
+            self.no_center_headings,
+            config.center_headings,
+        );
+        self.setext_headings = flag(
+            self.setext_headings,
+            self.no_setext_headings,
+            config.setext_headings,
+        );
+        self.title_from_frontmatter = flag(
+            self.title_from_frontmatter,
+            self.no_title_from_frontmatter,
+            config.title_from_frontmatter,
+        );
+        self.borders = flag(self.borders, self.no_borders, config.borders);
+        self.align_numbers = flag(
+            self.align_numbers,
+            self.no_align_numbers,
+            config.align_numbers,
+        );
+        self.no_highlight = flag(self.no_highlight, self.highlight, config.no_highlight);
+        self.line_numbers = flag(self.line_numbers, self.no_line_numbers, config.line_numbers);
+        self.boxed_code = flag(self.boxed_code, self.no_boxed_code, config.boxed_code);
+        self.no_images = flag(self.no_images, self.images, config.no_images);
+        self.task_progress = flag(
+            self.task_progress,
+            self.no_task_progress,
+            config.task_progress,
+        );
+        self.hyperlinks = flag(self.hyperlinks, self.no_hyperlinks, config.hyperlinks);
+        self.references = flag(self.references, self.no_references, config.references);
+        // --raw-html and --escape-html choose between the same modes
+        if !self.escape_html {
+            self.raw_html = flag(self.raw_html, self.no_raw_html, config.raw_html);
+        }
+        if !self.raw_html {
+            self.escape_html = flag(self.escape_html, self.no_escape_html, config.escape_html);
+        }
+        self.preserve_breaks = flag(
+            self.preserve_breaks,
+            self.no_preserve_breaks,
+            config.preserve_breaks,
+        );
+        self.compact = flag(self.compact, self.no_compact, config.compact);
+        self.pager = flag(self.pager, self.no_pager, config.pager);
+        // --color-emphasis and --theme choose between the same styles
+        if self.theme.is_none() && !self.color_emphasis {
+            self.theme = config.theme.clone();
+            self.color_emphasis = !self.no_color_emphasis && config.color_emphasis;
+        }
+        self
+    }
 }
 
 fn main() {
     let args = Args::parse();
+    if let Some(shell) = args.completions {
+        // Generated into a buffer because the generator panics if it can't write
+        let mut script = Vec::new();
+        clap_complete::generate(shell, &mut Args::command(), "prmd", &mut script);
+        print(&script);
+        return;
+    }
+    if args.manpage {
+        let mut page = Vec::new();
+        clap_mangen::Man::new(Args::command())
+            .render(&mut page)
+            .expect("rendering to memory doesn't fail");
+        print(&page);
+        return;
+    }
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("prmd: invalid config {}", message.trim_end());
+            process::exit(1);
+        }
+    };
+    let args = args.with_config(&config);
+    let comrak_options = comrak_options(&args.extensions);
+
+    let options = RenderOptions {
+        plain: plain(&args, &config),
+        width: Some(width(&args, &config)),
+        left_margin: args.margin,
+        preserve_breaks: args.preserve_breaks,
+        compact: args.compact,
+        heading_markers: args.heading_markers,
+        number_headings: args.number_headings,
+        center_headings: args.center_headings,
+        setext_headings: args.setext_headings,
+        title_from_frontmatter: args.title_from_frontmatter,
+        table_borders: args.borders,
+        table_align_numbers: args.align_numbers,
+        highlight: !args.no_highlight,
+        code_line_numbers: args.line_numbers,
+        code_block_boxed: args.boxed_code,
+        images: !args.no_images,
+        task_progress: args.task_progress,
+        hyperlinks: args.hyperlinks,
+        link_references: args.references,
+        tab_width: args.tab_width,
+        html: if args.raw_html {
+            HtmlMode::Raw
+        } else if args.escape_html {
+            HtmlMode::Escape
+        } else {
+            HtmlMode::Strip
+        },
+        truecolor: truecolor(),
+        theme: theme(&args),
+        ..RenderOptions::default()
+    };
+    let stdin = PathBuf::from("-");
+    let files = if args.files.is_empty() {
+        vec![&stdin]
+    } else {
+        args.files.iter().collect()
+    };
+    // Creating the output truncates it, so it can't be one of the files still to be read
+    if let Some(output) = &args.output {
+        let is_input = fs::canonicalize(output).is_ok_and(|output| {
+            files.iter().any(|file| {
+                file.as_path() != Path::new("-")
+                    && fs::canonicalize(file).is_ok_and(|path| path == output)
+            })
+        });
+        if is_input {
+            let message = "it's also an input file";
+            eprintln!("prmd: cannot write {}: {}", output.display(), message);
+            process::exit(1);
+        }
+    }
+    // Files are separated the same way a thematic break would be rendered
+    let divider = prmd::markdown_to_text_with("---", &options);
+    let renderer = Renderer::new()
+        .comrak_options(comrak_options)
+        .options(options);
+    let mut file = args
+        .output
+        .as_ref()
+        .map(|path| match fs::File::create(path) {
+            Ok(file) => BufWriter::new(file),
+            Err(error) => {
+                let message = io_error_message(&error);
+                eprintln!("prmd: cannot write {}: {}", path.display(), message);
+                process::exit(1);
+            }
+        });
+    let mut pager = if args.pager && file.is_none() && io::stdout().is_terminal() {
+        spawn_pager()
+    } else {
+        None
+    };
+    let mut stdout = io::stdout().lock();
+    let mut out: &mut dyn Write = match (&mut file, pager.as_mut()) {
+        (Some(file), _) => file,
+        (
+            None,
+            Some(Child {
+                stdin: Some(stdin), ..
+            }),
+        ) => stdin,
+        _ => &mut stdout,
+    };
+    let mut printed = false;
+    let mut failed = false;
+    for file in files {
+        let (name, input) = match file.to_str() {
+            Some(url) if remote::is_url(url) => (url.to_string(), remote::fetch(url)),
+            _ if file.as_path() != Path::new("-") => (
+                file.display().to_string(),
+                fs::read_to_string(file).map_err(|error| io_error_message(&error)),
+            ),
+            _ => (
+                String::from("stdin"),
+                io::read_to_string(io::stdin()).map_err(|error| io_error_message(&error)),
+            ),
+        };
+        let buffer = match input {
+            Ok(buffer) => buffer,
+            Err(message) => {
+                eprintln!("prmd: cannot read {}: {}", name, message);
+                failed = true;
+                continue;
+            }
+        };
+        if args.stats {
+            let stats = prmd::document_stats(&buffer);
+            let minutes = (stats.reading_time.as_secs_f64() / 60.0).ceil();
+            let prefix = if args.files.len() > 1 {
+                format!("{}: ", name)
+            } else {
+                String::default()
+            };
+            let line = format!(
+                "{}{} words, {} characters, {} min read\n",
+                prefix, stats.words, stats.characters, minutes
+            );
+            match out.write_all(line.as_bytes()) {
+                Err(error) if error.kind() == io::ErrorKind::BrokenPipe => break,
+                Err(error) => {
+                    eprintln!("prmd: cannot write output: {}", io_error_message(&error));
+                    process::exit(1);
+                }
+                Ok(()) => continue,
+            }
+        }
+        let mut result = Ok(());
+        if printed {
+            result = out.write_all(divider.as_bytes());
+        }
+        printed = true;
+        if args.toc {
+            result = result.and_then(|_| out.write_all(toc(&buffer).as_bytes()));
+        }
+        let result = result.and_then(|_| {
+            if !args.strict {
+                return renderer.write_diagnostics(&buffer, &mut out);
+            }
+            // Render it all first so nothing is printed if any of it can't be rendered
+            let mut text = Vec::new();
+            let errors = renderer.write_diagnostics(&buffer, &mut text)?;
+            if errors.is_empty() {
+                out.write_all(&text)?;
+            }
+            Ok(errors)
+        });
+        match result {
+            Ok(errors) if args.strict && !errors.is_empty() => {
+                for error in errors {
+                    eprintln!("prmd: cannot render {}: {}", name, error);
+                }
+                failed = true;
+            }
+            Ok(errors) if !args.quiet => {
+                errors.iter().for_each(|error| eprintln!("💔 {}", error));
+            }
+            Ok(_) => {}
+            // The reader went away (e.g. `prmd file.md | head`, or the pager was closed), so
+            // there's nobody to tell
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => break,
+            Err(error) => {
+                eprintln!("prmd: cannot write output: {}", io_error_message(&error));
+                process::exit(1);
+            }
+        }
+    }
+    if let Some(Err(error)) = file.map(|mut file| file.flush()) {
+        eprintln!("prmd: cannot write output: {}", io_error_message(&error));
+        process::exit(1);
+    }
+    if let Some(mut pager) = pager {
+        // Closing its input tells the pager there's no more to come
+        drop(pager.stdin.take());
+        let _ = pager.wait();
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Writes generated text to stdout, exiting if it can't be written
+fn print(text: &[u8]) {
+    match io::stdout().write_all(text) {
+        Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("prmd: cannot write output: {}", io_error_message(&error));
+            process::exit(1);
+        }
+        _ => {}
+    }
+}
+
+/// The extensions that --extensions can turn on or off
+const EXTENSIONS: [&str; 11] = [
+    "autolink",
+    "description-lists",
+    "footnotes",
+    "front-matter",
+    "math",
+    "strikethrough",
+    "superscript",
+    "table",
+    "tasklist",
+    "underline",
+    "wikilinks",
+];
+
+/// Parses an --extensions name into the extension and whether to turn it on
+fn extension(name: &str) -> Result<(String, bool), String> {
+    let (extension, on) = match name.strip_prefix("no-") {
+        Some(extension) => (extension, false),
+        None => (name, true),
+    };
+    if !EXTENSIONS.contains(&extension) {
+        return Err(format!("expected one of {}", EXTENSIONS.join(", ")));
+    }
+    Ok((extension.to_string(), on))
+}
+
+/// The default parsing options, with the extensions turned on or off in order
+fn comrak_options(extensions: &[(String, bool)]) -> comrak::Options<'static> {
+    let mut options = prmd::comrak_options();
+    let extension_options = &mut options.extension;
+    for (name, on) in extensions {
+        let on = *on;
+        match name.as_str() {
+            "autolink" => extension_options.autolink = on,
+            "description-lists" => extension_options.description_lists = on,
+            "footnotes" => extension_options.footnotes = on,
+            "front-matter" => {
+                extension_options.front_matter_delimiter = on.then(|| String::from("---"))
+            }
+            "math" => {
+                extension_options.math_dollars = on;
+                extension_options.math_code = on;
+            }
+            "strikethrough" => extension_options.strikethrough = on,
+            "superscript" => extension_options.superscript = on,
+            "table" => extension_options.table = on,
+            "tasklist" => extension_options.tasklist = on,
+            "underline" => extension_options.underline = on,
+            "wikilinks" => extension_options.wikilinks_title_after_pipe = on,
+            _ => {}
+        }
+    }
+    options
+}
+
+/// Starts $PAGER, or `less -R` so the ANSI formatting survives, with a pipe to write the
+/// output to. Returns `None` if it can't be started, to print directly instead.
+fn spawn_pager() -> Option<Child> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| String::from("less -R"));
+    let mut words = pager.split_whitespace();
+    let program = words.next()?;
+    Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+        .ok()
+}
+
+/// The document's headings, indented by level relative to the top-most one, followed by a
+/// blank line; empty if there are no headings.
+fn toc(md: &str) -> String {
+    let entries = prmd::build_toc(md);
+    let top = entries.iter().map(|(level, _)| *level).min().unwrap_or(1);
+    let mut text: String = entries
+        .iter()
+        .map(|(level, heading)| format!("{}{}\n", "  ".repeat(level - top), heading))
+        .collect();
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// Whether to render without ANSI formatting, decided by the first of these that applies:
+///
+/// 1. --force-color, or a CLICOLOR_FORCE environment variable that's set and not 0, formats
+/// 2. --plain doesn't
+/// 3. a non-empty NO_COLOR (following https://no-color.org), or CLICOLOR=0, doesn't
+/// 4. `plain = true` in the config file doesn't
+/// 5. otherwise it's formatted only if the output goes to a terminal, not a file or pipe
+fn plain(args: &Args, config: &Config) -> bool {
+    let clicolor_force = env::var_os("CLICOLOR_FORCE").is_some_and(|value| value != "0");
+    if args.force_color || clicolor_force {
+        return false;
+    }
+    if args.plain {
+        return true;
+    }
+    if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || env::var_os("CLICOLOR").is_some_and(|value| value == "0")
+    {
+        return true;
+    }
+    config.plain || args.output.is_some() || !io::stdout().is_terminal()
+}
+
+/// The theme named by --theme or read from the file it names, or the colored emphasis theme
+/// for --color-emphasis. Exits if the theme file can't be read.
+fn theme(args: &Args) -> Theme {
+    let Some(name) = &args.theme else {
+        return if args.color_emphasis {
+            Theme::colored_emphasis()
+        } else {
+            Theme::default()
+        };
+    };
+    if let Some(theme) = Theme::named(name) {
+        return theme;
+    }
+    let result = fs::read_to_string(name)
+        .map_err(|error| io_error_message(&error))
+        .and_then(|text| Theme::from_toml(&text).map_err(|error| error.to_string()));
+    match result {
+        Ok(theme) => theme,
+        Err(message) => {
+            eprintln!("prmd: invalid theme {}: {}", name, message.trim_end());
+            process::exit(1);
+        }
+    }
+}
+
+/// Whether the terminal says it supports 24-bit color in the COLORTERM environment variable
+fn truecolor() -> bool {
+    env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+}
+
+/// The width to wrap text to: --width if given, otherwise the COLUMNS environment variable,
+/// then the config file's width, then the terminal's width, falling back to 80 columns when
+/// there's no terminal to ask or the output goes to a file.
+fn width(args: &Args, config: &Config) -> usize {
+    let columns = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse::<usize>().ok())
+        .filter(|columns| *columns > 0);
+    args.width.or(columns).or(config.width).unwrap_or_else(|| {
+        match terminal_size::terminal_size() {
+            Some((terminal_size::Width(width), _)) if args.output.is_none() => usize::from(width),
+            _ => 80,
+        }
+    })
+}
 
-    let buffer = fs::read_to_string(args.file)
-        .expect("Couldn't read file");
-    print!("{}", prmd::markdown_to_text(buffer.as_str(), args.plain))
+/// The error's description without the trailing "(os error N)"
+fn io_error_message(error: &io::Error) -> String {
+    let message = error.to_string();
+    match message.find(" (os error ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
 }
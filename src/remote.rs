@@ -0,0 +1,31 @@
+/// Whether a file argument is an http or https URL rather than a path
+pub fn is_url(file: &str) -> bool {
+    let scheme = file.split_once("://").map(|(scheme, _)| scheme);
+    scheme.is_some_and(|scheme| {
+        scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+    })
+}
+
+/// Downloads the document at the URL, giving up if the server takes longer than 30 seconds
+#[cfg(feature = "remote")]
+pub fn fetch(url: &str) -> Result<String, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(30))
+        .build();
+    let response = agent.get(url).call().map_err(|error| match error {
+        ureq::Error::Status(code, response) => format!("{} {}", code, response.status_text()),
+        // The transport error's own message would repeat the URL
+        ureq::Error::Transport(transport) => match transport.message() {
+            Some(message) => format!("{}: {}", transport.kind(), message),
+            None => transport.kind().to_string(),
+        },
+    })?;
+    response
+        .into_string()
+        .map_err(|error| crate::io_error_message(&error))
+}
+
+#[cfg(not(feature = "remote"))]
+pub fn fetch(_url: &str) -> Result<String, String> {
+    Err(String::from("reading URLs requires the remote feature"))
+}
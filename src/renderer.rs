@@ -0,0 +1,112 @@
+use crate::{comrak_options, render_with_comrak_options, warn, RenderError, RenderOptions, Theme};
+use comrak::Options;
+use std::io::{self, Write};
+
+/// Renders Markdown with settings that are configured once and reused for every document.
+///
+/// ```
+/// let renderer = prmd::Renderer::new().plain(true).width(40);
+/// assert_eq!(renderer.render("*Hello*"), "Hello\n\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    comrak_options: Options<'static>,
+    render_options: RenderOptions,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    /// A renderer with the default options.
+    pub fn new() -> Renderer {
+        Renderer {
+            comrak_options: comrak_options(),
+            render_options: RenderOptions::default(),
+        }
+    }
+
+    /// Parses Markdown with these options instead of the defaults from
+    /// [`comrak_options`](crate::comrak_options), e.g. to enable more extensions.
+    pub fn comrak_options(mut self, comrak_options: Options<'static>) -> Renderer {
+        self.comrak_options = comrak_options;
+        self
+    }
+
+    /// Replaces all of the render options.
+    pub fn options(mut self, render_options: RenderOptions) -> Renderer {
+        self.render_options = render_options;
+        self
+    }
+
+    /// Render without ANSI formatting.
+    pub fn plain(mut self, plain: bool) -> Renderer {
+        self.render_options.plain = plain;
+        self
+    }
+
+    /// Wrap text to this many columns.
+    pub fn width(mut self, width: usize) -> Renderer {
+        self.render_options.width = Some(width);
+        self
+    }
+
+    /// Use these styles when not rendering plain text.
+    pub fn theme(mut self, theme: Theme) -> Renderer {
+        self.render_options.theme = theme;
+        self
+    }
+
+    /// Render links as OSC 8 terminal hyperlinks.
+    pub fn hyperlinks(mut self, hyperlinks: bool) -> Renderer {
+        self.render_options.hyperlinks = hyperlinks;
+        self
+    }
+
+    /// Draw box borders around and between table cells.
+    pub fn table_borders(mut self, table_borders: bool) -> Renderer {
+        self.render_options.table_borders = table_borders;
+        self
+    }
+
+    /// Syntax highlight fenced code blocks.
+    pub fn highlight(mut self, highlight: bool) -> Renderer {
+        self.render_options.highlight = highlight;
+        self
+    }
+
+    /// Number links and list their URLs in a References section at the end.
+    pub fn link_references(mut self, link_references: bool) -> Renderer {
+        self.render_options.link_references = link_references;
+        self
+    }
+
+    /// Converts the Markdown to text, printing a warning for each node that can't be rendered.
+    pub fn render(&self, md: &str) -> String {
+        let mut buffer: Vec<u8> = vec![];
+        self.write(md, &mut buffer)
+            .expect("Couldn't write to buffer");
+        String::from_utf8(buffer).expect("Rendered text isn't UTF-8")
+    }
+
+    /// Renders the Markdown to `out` one top-level block at a time.
+    pub fn write<W: Write>(&self, md: &str, out: &mut W) -> io::Result<()> {
+        let errors =
+            render_with_comrak_options(md, &self.comrak_options, &self.render_options, out)?;
+        warn(&errors);
+        Ok(())
+    }
+
+    /// Like `write`, but returns the nodes that couldn't be rendered instead of printing a
+    /// warning for each.
+    pub fn write_diagnostics<W: Write>(
+        &self,
+        md: &str,
+        out: &mut W,
+    ) -> io::Result<Vec<RenderError>> {
+        render_with_comrak_options(md, &self.comrak_options, &self.render_options, out)
+    }
+}
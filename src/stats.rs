@@ -0,0 +1,28 @@
+use crate::markdown_to_plaintext;
+use std::time::Duration;
+
+/// How long a document is, measured on its text without the Markdown syntax or URLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentStats {
+    pub words: usize,
+    /// The number of characters, counting each run of whitespace between words as one space
+    pub characters: usize,
+    /// How long it takes to read the words at 200 words per minute
+    pub reading_time: Duration,
+}
+
+/// The number of words read per minute for `DocumentStats::reading_time`
+const WORDS_PER_MINUTE: u64 = 200;
+
+/// Counts the words and characters in the document's text.
+pub fn document_stats(md: &str) -> DocumentStats {
+    let text = markdown_to_plaintext(md);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let characters = words.iter().map(|word| word.chars().count()).sum::<usize>()
+        + words.len().saturating_sub(1);
+    DocumentStats {
+        words: words.len(),
+        characters,
+        reading_time: Duration::from_secs(words.len() as u64 * 60) / WORDS_PER_MINUTE as u32,
+    }
+}
@@ -0,0 +1,200 @@
+use ansi_term::{Color, Style};
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A style as theme files and serialized options write it: its colors, and the attributes
+/// that are turned on
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StyleSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fg: Option<ColorSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bg: Option<ColorSpec>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    bold: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    dimmed: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    italic: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    underline: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    blink: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    reverse: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    hidden: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    strikethrough: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl From<StyleSpec> for Style {
+    fn from(spec: StyleSpec) -> Style {
+        Style {
+            foreground: spec.fg.map(|color| color.0),
+            background: spec.bg.map(|color| color.0),
+            is_bold: spec.bold,
+            is_dimmed: spec.dimmed,
+            is_italic: spec.italic,
+            is_underline: spec.underline,
+            is_blink: spec.blink,
+            is_reverse: spec.reverse,
+            is_hidden: spec.hidden,
+            is_strikethrough: spec.strikethrough,
+        }
+    }
+}
+
+impl From<Style> for StyleSpec {
+    fn from(style: Style) -> StyleSpec {
+        StyleSpec {
+            fg: style.foreground.map(ColorSpec),
+            bg: style.background.map(ColorSpec),
+            bold: style.is_bold,
+            dimmed: style.is_dimmed,
+            italic: style.is_italic,
+            underline: style.is_underline,
+            blink: style.is_blink,
+            reverse: style.is_reverse,
+            hidden: style.is_hidden,
+            strikethrough: style.is_strikethrough,
+        }
+    }
+}
+
+/// A color name like `"cyan"`, a 256-color palette index, or an RGB color like `"#ff8800"`
+struct ColorSpec(Color);
+
+impl Serialize for ColorSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self.0 {
+            Color::Black => "black",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Purple => "purple",
+            Color::Cyan => "cyan",
+            Color::White => "white",
+            Color::Fixed(index) => return serializer.serialize_u8(index),
+            Color::RGB(r, g, b) => {
+                return serializer.serialize_str(&format!("#{:02x}{:02x}{:02x}", r, g, b))
+            }
+        };
+        serializer.serialize_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+struct ColorVisitor;
+
+impl de::Visitor<'_> for ColorVisitor {
+    type Value = ColorSpec;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a color name, a number from 0 to 255 or #rrggbb")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<ColorSpec, E> {
+        match u8::try_from(value) {
+            Ok(index) => Ok(ColorSpec(Color::Fixed(index))),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Signed(value), &self)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<ColorSpec, E> {
+        match u8::try_from(value) {
+            Ok(index) => Ok(ColorSpec(Color::Fixed(index))),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<ColorSpec, E> {
+        match parse_color(value) {
+            Some(color) => Ok(ColorSpec(color)),
+            None => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+        }
+    }
+}
+
+fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+        return Some(Color::RGB(channel(0)?, channel(2)?, channel(4)?));
+    }
+    match name.to_ascii_lowercase().as_str() {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "purple" | "magenta" => Some(Color::Purple),
+        "cyan" => Some(Color::Cyan),
+        "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+/// For `#[serde(with)]` on a `Style` field
+pub(crate) mod style {
+    use super::StyleSpec;
+    use ansi_term::Style;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        style: &Style,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        StyleSpec::from(*style).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Style, D::Error> {
+        StyleSpec::deserialize(deserializer).map(Style::from)
+    }
+}
+
+/// For `#[serde(with)]` on a list of styles, like the headings' array or the blockquote
+/// leads' `Vec`
+pub(crate) mod styles {
+    use super::StyleSpec;
+    use ansi_term::Style;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S, T>(styles: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[Style]>,
+    {
+        serializer.collect_seq(styles.as_ref().iter().map(|style| StyleSpec::from(*style)))
+    }
+
+    pub(crate) fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<Style>>,
+    {
+        let specs = Vec::<StyleSpec>::deserialize(deserializer)?;
+        let count = specs.len();
+        let styles: Vec<Style> = specs.into_iter().map(Style::from).collect();
+        // Only a fixed-size array, with one style per heading level, can have the wrong length
+        T::try_from(styles).map_err(|_| D::Error::invalid_length(count, &"one style per level"))
+    }
+}
@@ -0,0 +1,232 @@
+use ansi_term::{Color, Style};
+
+/// The styles used for each kind of element when rendering with ANSI formatting. With the
+/// `serde` feature, a theme can be serialized with each style written the way theme files
+/// write them.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Theme {
+    /// Heading styles for levels 1 through 6
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::styles"))]
+    pub headings: [Style; 6],
+    /// The `#` markers in front of headings
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub heading_marker: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub emphasis: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub strong: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub underline: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub strikethrough: Style,
+    /// TeX source of inline and display math
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub math: Style,
+    /// Inline code spans
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub code: Style,
+    /// Code block lines; its background is kept under syntax highlighted lines
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub code_block: Style,
+    /// Comments in syntax highlighted code blocks; without a foreground color, the
+    /// highlighting's color is kept
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub code_comment: Style,
+    /// The `[language]` line above a code block
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub code_block_info: Style,
+    /// Code block line numbers
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub line_number: Style,
+    /// Link and image text
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub link: Style,
+    /// The text of `mailto:` links to email addresses
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub email: Style,
+    /// The character drawn in front of each level of a blockquote
+    pub blockquote_lead: char,
+    /// Styles for the blockquote lead of each nesting level, repeating from the first for
+    /// blockquotes nested deeper than there are styles
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::styles"))]
+    pub blockquote_leads: Vec<Style>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub table_header: Style,
+    /// Every other row of a table's body, starting with the second, e.g. with a background
+    /// color to make the rows easier to follow; unstyled by default
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub table_stripe: Style,
+    /// The checkbox of a checked task list item
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub task_checked: Style,
+    /// The headings of the Footnotes and References sections
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub section_heading: Style,
+    /// Titles of GitHub alert blockquotes like `[!NOTE]`
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub alert_note: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub alert_tip: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub alert_important: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub alert_warning: Style,
+    #[cfg_attr(feature = "serde", serde(with = "crate::style_spec::style"))]
+    pub alert_caution: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            headings: [
+                Style::new().bold().underline(),
+                Style::new().bold().italic(),
+                Style::new().italic().underline(),
+                Style::new().underline(),
+                Style::new().italic(),
+                Style::new().italic(),
+            ],
+            heading_marker: Style::new().dimmed(),
+            emphasis: Style::new().italic(),
+            strong: Style::new().bold(),
+            underline: Style::new().underline(),
+            strikethrough: Style::new().strikethrough(),
+            math: Color::Cyan.italic(),
+            code: Style::new().fg(Color::White).bold().on(Color::Fixed(238)),
+            code_block: Style::new().fg(Color::White).bold().on(Color::Fixed(238)),
+            code_comment: Style::new().dimmed(),
+            code_block_info: Style::new().reverse(),
+            line_number: Style::new().dimmed(),
+            link: Style::new().underline(),
+            email: Color::Cyan.underline(),
+            blockquote_lead: '│',
+            blockquote_leads: vec![
+                Color::Fixed(67).normal(),
+                Color::Fixed(103).normal(),
+                Color::Fixed(139).normal(),
+            ],
+            table_header: Style::new().bold().underline(),
+            table_stripe: Style::new(),
+            task_checked: Style::new().fg(Color::Green),
+            section_heading: Style::new().bold(),
+            alert_note: Color::Blue.bold(),
+            alert_tip: Color::Green.bold(),
+            alert_important: Color::Purple.bold(),
+            alert_warning: Color::Yellow.bold(),
+            alert_caution: Color::Red.bold(),
+        }
+    }
+}
+
+impl Theme {
+    /// The built-in theme with the given name: `dark` or `light`.
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    /// The default theme, for terminals with a dark background.
+    pub fn dark() -> Theme {
+        Theme::default()
+    }
+
+    /// A theme for terminals with a light background, with dark text on a light gray
+    /// background for code.
+    pub fn light() -> Theme {
+        Theme {
+            code: Style::new().fg(Color::Black).on(Color::Fixed(254)),
+            code_block: Style::new().fg(Color::Black).on(Color::Fixed(254)),
+            line_number: Style::new().fg(Color::Fixed(244)),
+            task_checked: Style::new().fg(Color::Fixed(28)),
+            ..Theme::default()
+        }
+    }
+
+    /// The default theme with emphasis shown in cyan instead of italic and strong text in
+    /// bright white instead of bold, for terminals that can't show italic or bold.
+    pub fn colored_emphasis() -> Theme {
+        Theme {
+            emphasis: Color::Cyan.normal(),
+            strong: Color::Fixed(15).normal(),
+            ..Theme::default()
+        }
+    }
+}
+
+impl Theme {
+    /// The theme with any 24-bit RGB colors replaced by the nearest of the 256 indexed
+    /// colors, for terminals that don't support truecolor.
+    pub fn to_256_colors(&self) -> Theme {
+        let style = |style: Style| Style {
+            foreground: style.foreground.map(nearest_256_color),
+            background: style.background.map(nearest_256_color),
+            ..style
+        };
+        Theme {
+            headings: self.headings.map(style),
+            heading_marker: style(self.heading_marker),
+            emphasis: style(self.emphasis),
+            strong: style(self.strong),
+            underline: style(self.underline),
+            strikethrough: style(self.strikethrough),
+            math: style(self.math),
+            code: style(self.code),
+            code_block: style(self.code_block),
+            code_comment: style(self.code_comment),
+            code_block_info: style(self.code_block_info),
+            line_number: style(self.line_number),
+            link: style(self.link),
+            email: style(self.email),
+            blockquote_lead: self.blockquote_lead,
+            blockquote_leads: self.blockquote_leads.iter().copied().map(style).collect(),
+            table_header: style(self.table_header),
+            table_stripe: style(self.table_stripe),
+            task_checked: style(self.task_checked),
+            section_heading: style(self.section_heading),
+            alert_note: style(self.alert_note),
+            alert_tip: style(self.alert_tip),
+            alert_important: style(self.alert_important),
+            alert_warning: style(self.alert_warning),
+            alert_caution: style(self.alert_caution),
+        }
+    }
+}
+
+/// The closest color in the 6×6×6 cube or the grayscale ramp of the 256-color palette to an
+/// RGB color; other colors are returned as they are.
+pub(crate) fn nearest_256_color(color: Color) -> Color {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let Color::RGB(r, g, b) = color else {
+        return color;
+    };
+    let distance = |(r1, g1, b1): (u8, u8, u8)| {
+        [(r, r1), (g, g1), (b, b1)]
+            .iter()
+            .map(|(a, b)| (i32::from(*a) - i32::from(*b)).pow(2))
+            .sum::<i32>()
+    };
+    let level = |value: u8| {
+        (0..LEVELS.len())
+            .min_by_key(|&index| (i32::from(LEVELS[index]) - i32::from(value)).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let gray_index = (average.saturating_sub(8) + 5) / 10;
+    let gray_index = gray_index.min(23) as u8;
+    let gray = 8 + 10 * gray_index;
+    if distance((gray, gray, gray)) < distance(cube) {
+        Color::Fixed(232 + gray_index)
+    } else {
+        Color::Fixed(16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8)
+    }
+}
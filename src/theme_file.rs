@@ -0,0 +1,127 @@
+use crate::style_spec::StyleSpec;
+use crate::Theme;
+use ansi_term::Style;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+/// A theme file couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeError {
+    message: String,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ThemeError {}
+
+/// The elements a theme file can style, named after the `Theme` fields, with `heading1` to
+/// `heading6` for the heading levels
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    heading1: Option<StyleSpec>,
+    heading2: Option<StyleSpec>,
+    heading3: Option<StyleSpec>,
+    heading4: Option<StyleSpec>,
+    heading5: Option<StyleSpec>,
+    heading6: Option<StyleSpec>,
+    heading_marker: Option<StyleSpec>,
+    emphasis: Option<StyleSpec>,
+    strong: Option<StyleSpec>,
+    underline: Option<StyleSpec>,
+    strikethrough: Option<StyleSpec>,
+    math: Option<StyleSpec>,
+    code: Option<StyleSpec>,
+    code_block: Option<StyleSpec>,
+    code_comment: Option<StyleSpec>,
+    code_block_info: Option<StyleSpec>,
+    line_number: Option<StyleSpec>,
+    link: Option<StyleSpec>,
+    email: Option<StyleSpec>,
+    blockquote_lead: Option<char>,
+    /// Replaces all of the nesting levels' styles
+    blockquote_leads: Option<Vec<StyleSpec>>,
+    table_header: Option<StyleSpec>,
+    table_stripe: Option<StyleSpec>,
+    task_checked: Option<StyleSpec>,
+    section_heading: Option<StyleSpec>,
+    alert_note: Option<StyleSpec>,
+    alert_tip: Option<StyleSpec>,
+    alert_important: Option<StyleSpec>,
+    alert_warning: Option<StyleSpec>,
+    alert_caution: Option<StyleSpec>,
+}
+
+impl Theme {
+    /// Reads a theme from TOML, where each element is a table of its style, e.g.
+    ///
+    /// ```toml
+    /// [heading1]
+    /// fg = "cyan"
+    /// bold = true
+    ///
+    /// [code_block]
+    /// fg = 252
+    /// bg = "#202020"
+    /// ```
+    ///
+    /// Elements that aren't in the file keep their default styles. Requires the `theme-file`
+    /// feature.
+    pub fn from_toml(text: &str) -> Result<Theme, ThemeError> {
+        let file: ThemeFile = toml::from_str(text).map_err(|error| ThemeError {
+            message: error.to_string(),
+        })?;
+        let mut theme = Theme::default();
+        let headings = [
+            file.heading1,
+            file.heading2,
+            file.heading3,
+            file.heading4,
+            file.heading5,
+            file.heading6,
+        ];
+        for (style, spec) in theme.headings.iter_mut().zip(headings) {
+            set(style, spec);
+        }
+        set(&mut theme.heading_marker, file.heading_marker);
+        set(&mut theme.emphasis, file.emphasis);
+        set(&mut theme.strong, file.strong);
+        set(&mut theme.underline, file.underline);
+        set(&mut theme.strikethrough, file.strikethrough);
+        set(&mut theme.math, file.math);
+        set(&mut theme.code, file.code);
+        set(&mut theme.code_block, file.code_block);
+        set(&mut theme.code_comment, file.code_comment);
+        set(&mut theme.code_block_info, file.code_block_info);
+        set(&mut theme.line_number, file.line_number);
+        set(&mut theme.link, file.link);
+        set(&mut theme.email, file.email);
+        if let Some(lead) = file.blockquote_lead {
+            theme.blockquote_lead = lead;
+        }
+        if let Some(leads) = file.blockquote_leads {
+            theme.blockquote_leads = leads.into_iter().map(Style::from).collect();
+        }
+        set(&mut theme.table_header, file.table_header);
+        set(&mut theme.table_stripe, file.table_stripe);
+        set(&mut theme.task_checked, file.task_checked);
+        set(&mut theme.section_heading, file.section_heading);
+        set(&mut theme.alert_note, file.alert_note);
+        set(&mut theme.alert_tip, file.alert_tip);
+        set(&mut theme.alert_important, file.alert_important);
+        set(&mut theme.alert_warning, file.alert_warning);
+        set(&mut theme.alert_caution, file.alert_caution);
+        Ok(theme)
+    }
+}
+
+fn set(style: &mut Style, spec: Option<StyleSpec>) {
+    if let Some(spec) = spec {
+        *style = Style::from(spec);
+    }
+}
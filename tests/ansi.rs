@@ -0,0 +1,106 @@
+use prmd::{markdown_to_text_with, RenderOptions};
+
+/// Asserts that no line leaves a style switched on for the next one
+fn assert_lines_reset(text: &str) {
+    for line in text.lines() {
+        let last_style = line.rfind("\x1b[").map(|index| &line[index..]);
+        if let Some(last_style) = last_style.filter(|style| !style.starts_with("\x1b[K")) {
+            assert!(
+                last_style.starts_with("\x1b[0m"),
+                "style isn't reset: {:?}",
+                line
+            );
+        }
+    }
+}
+
+fn render(md: &str) -> String {
+    let options = RenderOptions {
+        width: Some(20),
+        code_line_numbers: true,
+        ..RenderOptions::default()
+    };
+    markdown_to_text_with(md, &options)
+}
+
+#[test]
+fn code_block_lines_end_with_a_reset() {
+    let text =
+        render("```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n```\nplain\ncode\n```\n");
+    let code_lines = text.lines().filter(|line| !line.is_empty()).count();
+    assert_eq!(code_lines, 6);
+    assert_lines_reset(&text);
+}
+
+#[test]
+fn blockquote_lines_end_with_a_reset() {
+    assert_lines_reset(&render(
+        "> **Wrapped bold text in a quote** and *more*\n>\n> > [!NOTE]\n> > Nested `code`\n",
+    ));
+}
+
+#[test]
+fn table_lines_end_with_a_reset() {
+    assert_lines_reset(&render("| **a** | b |\n|---|---|\n| *c* | `d` |\n"));
+}
+
+/// The text's visible characters, each with whether it's shown bold and italic
+fn bold_and_italic(text: &str) -> Vec<(char, bool, bool)> {
+    let (mut bold, mut italic) = (false, false);
+    let mut chars = text.chars();
+    let mut shown = vec![];
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            shown.push((c, bold, italic));
+            continue;
+        }
+        let sequence: String = chars.by_ref().take_while(|c| *c != 'm').collect();
+        for parameter in sequence.trim_start_matches('[').split(';') {
+            match parameter {
+                "0" | "" => (bold, italic) = (false, false),
+                "1" => bold = true,
+                "3" => italic = true,
+                _ => {}
+            }
+        }
+    }
+    shown
+}
+
+#[test]
+fn nested_emphasis_combines_styles() {
+    let options = RenderOptions::default();
+    for md in ["***x***", "**_x_**", "_**x**_"] {
+        let text = markdown_to_text_with(md, &options);
+        assert_eq!(
+            bold_and_italic(text.trim_end())[0],
+            ('x', true, true),
+            "{}",
+            md
+        );
+    }
+    let text = markdown_to_text_with("**a *b* c**", &options);
+    let shown = bold_and_italic(text.trim_end());
+    assert_eq!(
+        shown,
+        [
+            ('a', true, false),
+            (' ', true, false),
+            ('b', true, true),
+            (' ', true, false),
+            ('c', true, false)
+        ]
+    );
+}
+
+#[test]
+fn styles_are_only_written_where_they_change() {
+    let options = RenderOptions::default();
+    let text = markdown_to_text_with("**a *b* c**", &options);
+    assert_eq!(
+        text.trim_end(),
+        "\x1b[1ma \x1b[0m\x1b[1;3mb\x1b[0m\x1b[1m c\x1b[0m"
+    );
+    let text = markdown_to_text_with("*one\ntwo*", &options);
+    assert_eq!(text.trim_end(), "\x1b[3mone two\x1b[0m");
+}
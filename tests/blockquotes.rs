@@ -0,0 +1,42 @@
+use prmd::{markdown_to_text_with, RenderOptions};
+
+fn render(md: &str) -> String {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    markdown_to_text_with(md, &options)
+}
+
+#[test]
+fn alerts_are_shown_with_their_symbol_and_title() {
+    assert_eq!(
+        render("> [!WARNING]\n> Be *careful*.\n"),
+        "│ ⚠ WARNING\n│ Be careful.\n\n"
+    );
+    assert_eq!(
+        render("> [!NOTE]\n> Read this.\n"),
+        "│ ⓘ NOTE\n│ Read this.\n\n"
+    );
+}
+
+#[test]
+fn alerts_with_only_a_marker_are_just_a_title() {
+    assert_eq!(render("> [!WARNING]\n"), "│ ⚠ WARNING\n\n");
+    assert_eq!(
+        render("> [!NOTE]\n>\n> Read this.\n"),
+        "│ ⓘ NOTE\n│ Read this.\n\n"
+    );
+}
+
+#[test]
+fn quotes_without_an_alert_marker_are_left_as_they_are() {
+    assert_eq!(
+        render("> Just a quote\n> over lines\n>\n> > Nested\n"),
+        "│ Just a quote over lines\n│\n│ │ Nested\n\n"
+    );
+    assert_eq!(
+        render("> [!NOPE]\n> Not an alert\n"),
+        "│ [!NOPE] Not an alert\n\n"
+    );
+}
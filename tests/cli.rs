@@ -0,0 +1,34 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn output_refuses_to_overwrite_an_input() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("same.md");
+    fs::write(&path, "# Title\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_prmd"))
+        .arg(&path)
+        .arg("-o")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("it's also an input file"), "{}", stderr);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "# Title\n");
+}
+
+#[test]
+fn stats_round_a_short_read_up_to_a_minute() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("short.md");
+    fs::write(&path, "Three short words\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_prmd"))
+        .arg("--stats")
+        .arg(&path)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("3 words"), "{}", stdout);
+    assert!(stdout.contains("1 min read"), "{}", stdout);
+}
@@ -0,0 +1,78 @@
+use prmd::{markdown_to_text_with, strip_ansi, RenderOptions};
+
+#[test]
+fn boxed_code_blocks_pad_lines_to_the_longest() {
+    let options = RenderOptions {
+        plain: true,
+        code_block_boxed: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("```\nlonger line\nshort\n```\n", &options),
+        "╔═════════════╗\n║ longer line ║\n║ short       ║\n╚═════════════╝\n\n"
+    );
+}
+
+#[test]
+fn tabs_expand_to_tab_stops() {
+    let options = RenderOptions {
+        plain: true,
+        tab_width: 4,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("```\n\tone\nab\tcd\n```\n", &options),
+        "║     one\n║ ab  cd\n\n"
+    );
+}
+
+#[test]
+fn tabs_after_inline_markup_expand_from_the_start_of_the_line() {
+    let options = RenderOptions {
+        plain: true,
+        tab_width: 4,
+        ..RenderOptions::default()
+    };
+    assert_eq!(markdown_to_text_with("**a**\tb\n", &options), "a   b\n\n");
+    assert_eq!(markdown_to_text_with("`x`y\tz\n", &options), "xy  z\n\n");
+    let styled = markdown_to_text_with(
+        "**a**\tb\n",
+        &RenderOptions {
+            plain: false,
+            ..options
+        },
+    );
+    assert_eq!(strip_ansi(&styled), "a   b\n\n");
+}
+
+#[test]
+fn code_blocks_in_blockquotes_keep_the_quote_lead() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let md = "> Run this:\n>\n> ```rust\n> fn main() {\n>\n> }\n> ```\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "│ Run this:\n│\n│ [rust]\n│ ║ fn main() {\n│ ║ \n│ ║ }\n\n"
+    );
+}
+
+#[cfg(feature = "highlight")]
+#[test]
+fn highlighted_comments_use_the_comment_style() {
+    let text = markdown_to_text_with(
+        "```rust\nlet x = 1; // one\n```\n",
+        &RenderOptions::default(),
+    );
+    let comment = text.find("//").unwrap();
+    let style = &text[text[..comment].rfind("\x1b[").unwrap()..comment];
+    assert!(
+        style.starts_with("\x1b[2;"),
+        "comment isn't dimmed: {:?}",
+        text
+    );
+    let code = text.find("let").unwrap();
+    let style = &text[text[..code].rfind("\x1b[").unwrap()..code];
+    assert!(!style.starts_with("\x1b[2;"), "code is dimmed: {:?}", text);
+}
@@ -0,0 +1,28 @@
+use prmd::{markdown_to_text_with, RenderOptions};
+
+#[test]
+fn definitions_are_indented_under_their_terms() {
+    let options = RenderOptions {
+        plain: true,
+        width: Some(20),
+        ..RenderOptions::default()
+    };
+    let md = "Term\n\n: First definition of the term\n\n  More\n\nOther\n\n: Its definition\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "Term\n  : First definition\n    of the term\n    More\n\nOther\n  : Its definition\n\n"
+    );
+}
+
+#[test]
+fn definitions_can_hold_any_block() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let md = "Term\n\n: Details\n\n  > quote\n\n  - a\n  - b\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "Term\n  : Details\n    │ quote\n    • a\n    • b\n\n"
+    );
+}
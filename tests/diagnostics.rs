@@ -0,0 +1,70 @@
+use prmd::{
+    comrak_options, markdown_to_text_diagnostics, markdown_to_text_with, try_markdown_to_text,
+    RenderError, RenderOptions, Renderer,
+};
+
+#[test]
+fn unexpected_nodes_are_returned_with_a_placeholder() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    // Multiline block quotes aren't an extension that prmd parses or renders by default
+    let mut comrak_options = comrak_options();
+    comrak_options.extension.multiline_block_quotes = true;
+    let renderer = Renderer::new()
+        .comrak_options(comrak_options)
+        .options(options);
+    let mut text: Vec<u8> = vec![];
+    let diagnostics = renderer
+        .write_diagnostics("Text\n\n>>>\nquote\n>>>\n", &mut text)
+        .unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("💔"));
+    assert_eq!(
+        diagnostics,
+        [RenderError {
+            node: String::from("MultilineBlockQuote"),
+            parent: "Document",
+            line: 3,
+            column: 1,
+        }]
+    );
+}
+
+#[test]
+fn supported_markdown_has_no_diagnostics() {
+    let options = RenderOptions::default();
+    let (_, diagnostics) = markdown_to_text_diagnostics("# Title\n\n- *item*\n", &options);
+    assert!(diagnostics.is_empty());
+    let md = "Term\n\n: Details\n\n  > quote\n";
+    let (_, diagnostics) = markdown_to_text_diagnostics(md, &options);
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn try_markdown_to_text_returns_the_text_when_everything_renders() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let md = "# Title\n\n- *item*\n";
+    assert_eq!(
+        try_markdown_to_text(md, &options),
+        Ok(markdown_to_text_with(md, &options))
+    );
+}
+
+#[test]
+fn try_markdown_to_text_returns_an_unexpected_node_as_the_error() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    // The Markdown in a details block is parsed on its own, so a footnote defined there
+    // isn't moved to the end of the document with the others
+    let md = "Text\n\n<details><summary>S</summary>\nx[^a]\n[^a]: note\n</details>\n";
+    let error = try_markdown_to_text(md, &options).unwrap_err();
+    assert_eq!(error.node, "FootnoteDefinition");
+    assert_eq!(error.parent, "Details");
+}
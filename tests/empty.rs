@@ -0,0 +1,23 @@
+use prmd::{markdown_to_text_diagnostics, RenderOptions};
+
+#[test]
+fn documents_without_content_render_as_nothing() {
+    let inputs = [
+        "",
+        "   \n\t\n",
+        "<!-- a comment -->\n",
+        "[label]: https://example.com\n",
+    ];
+    for md in inputs {
+        for plain in [true, false] {
+            let options = RenderOptions {
+                plain,
+                link_references: !plain,
+                ..RenderOptions::default()
+            };
+            let (text, errors) = markdown_to_text_diagnostics(md, &options);
+            assert_eq!(text, "", "{:?}", md);
+            assert!(errors.is_empty(), "{:?}: {:?}", md, errors);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use prmd::markdown_to_text;
+
+#[test]
+fn footnotes_are_numbered_by_their_first_reference() {
+    let md = "Later[^b] and first[^a].\n\n[^a]: Defined first.\n[^b]: Defined second.\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "Later[1] and first[2].\n\nFootnotes\n\n[1] Defined second.\n[2] Defined first.\n\n"
+    );
+}
+
+#[test]
+fn repeated_references_share_a_number_and_a_definition() {
+    let md =
+        "One[^note], two[^other] and three[^note].\n\n[^note]: The note.\n[^other]: Another.\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "One[1], two[2] and three[1].\n\nFootnotes\n\n[1] The note.\n[2] Another.\n\n"
+    );
+}
+
+#[test]
+fn unreferenced_definitions_are_left_out() {
+    let md = "Text[^used].\n\n[^unused]: Never referenced.\n[^used]: Referenced.\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "Text[1].\n\nFootnotes\n\n[1] Referenced.\n\n"
+    );
+}
+
+#[test]
+fn definitions_can_hold_a_list() {
+    let md = "Text[^a].\n\n[^a]: A list:\n\n    - one\n    - two\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "Text[1].\n\nFootnotes\n\n[1] A list:\n\n    • one\n    • two\n\n"
+    );
+}
+
+#[test]
+fn definitions_can_hold_a_code_block() {
+    let md = "Text[^a].\n\n[^a]: Some code:\n\n    ```\n    let x = 1;\n    ```\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "Text[1].\n\nFootnotes\n\n[1] Some code:\n\n    ║ let x = 1;\n\n"
+    );
+}
+
+#[test]
+fn definitions_are_spaced_apart_when_one_has_several_blocks() {
+    let md = "One[^a] and two[^b].\n\n[^a]: First.\n\n    Second.\n\n[^b]: Other.\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "One[1] and two[2].\n\nFootnotes\n\n[1] First.\n\n    Second.\n\n[2] Other.\n\n"
+    );
+}
@@ -0,0 +1,58 @@
+use prmd::{build_toc, markdown_to_text, markdown_to_text_with, RenderOptions};
+
+#[test]
+fn yaml_front_matter_is_left_out() {
+    let md = "---\ntitle: Notes\ntags: [a, b]\n---\n\n# Heading\n\ntext\n";
+    assert_eq!(markdown_to_text(md, true), "Heading\n\ntext\n\n");
+}
+
+#[test]
+fn toml_front_matter_is_left_out() {
+    let md = "+++\ntitle = \"Notes\"\n+++\ntext\n";
+    assert_eq!(markdown_to_text(md, true), "text\n\n");
+}
+
+#[test]
+fn thematic_breaks_after_the_start_are_not_front_matter() {
+    let md = "text\n\n---\n\nmore\n\n---\n";
+    assert_eq!(markdown_to_text(md, true), "text\n\n───\nmore\n\n───\n");
+}
+
+#[test]
+fn front_matter_is_not_in_the_table_of_contents() {
+    let md = "---\ntitle: Notes\n---\n# Heading\n";
+    assert_eq!(build_toc(md), [(1, String::from("Heading"))]);
+}
+
+fn with_title(md: &str) -> String {
+    let options = RenderOptions {
+        plain: true,
+        title_from_frontmatter: true,
+        ..RenderOptions::default()
+    };
+    markdown_to_text_with(md, &options)
+}
+
+#[test]
+fn front_matter_titles_are_shown_as_headings() {
+    let md = "---\ndate: 2024-01-01\ntitle: \"A *literal* title\" # comment\n---\ntext\n";
+    assert_eq!(with_title(md), "A *literal* title\n\ntext\n\n");
+    let md = "+++\ntitle = 'Notes'\n+++\ntext\n";
+    assert_eq!(with_title(md), "Notes\n\ntext\n\n");
+    let md = "---\ntitle: Plain title # comment\n---\ntext\n";
+    assert_eq!(with_title(md), "Plain title\n\ntext\n\n");
+}
+
+#[test]
+fn front_matter_without_a_readable_title_is_still_left_out() {
+    let inputs = [
+        "---\nauthor: someone\n---\ntext\n",
+        "---\npage:\n  title: nested\n---\ntext\n",
+        "---\ntitle: >\n  folded\n---\ntext\n",
+        "---\ntitle: \"unterminated\n---\ntext\n",
+        "+++\n[page]\ntitle = \"in a table\"\n+++\ntext\n",
+    ];
+    for md in inputs {
+        assert_eq!(with_title(md), "text\n\n", "{:?}", md);
+    }
+}
@@ -0,0 +1,67 @@
+use prmd::{markdown_to_text_with, strip_ansi, RenderOptions};
+
+#[test]
+fn headings_are_numbered_by_section() {
+    let options = RenderOptions {
+        plain: true,
+        number_headings: true,
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with("# A\n## B\n## C\n#### D\n# E\n## F\n", &options);
+    let lines: Vec<&str> = text.lines().filter(|line| !line.is_empty()).collect();
+    assert_eq!(
+        lines,
+        ["1 A", "1.1 B", "1.2 C", "1.2.0.1 D", "2 E", "2.1 F"]
+    );
+}
+
+#[test]
+fn heading_markers_show_the_level() {
+    let options = RenderOptions {
+        plain: true,
+        heading_markers: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("# Title\n\n### Detail\n", &options),
+        "# Title\n\n### Detail\n\n"
+    );
+}
+
+#[test]
+fn centered_headings_are_padded_by_their_visible_width() {
+    let options = RenderOptions {
+        width: Some(20),
+        center_headings: true,
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with("# A *b* `c`\n\ntext\n", &options);
+    assert_eq!(strip_ansi(&text), "       A b c\n\ntext\n\n");
+}
+
+#[test]
+fn setext_headings_are_underlined_to_their_width() {
+    let options = RenderOptions {
+        plain: true,
+        setext_headings: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("# Title\n## A `b`\n### C\n", &options),
+        "Title\n=====\n\nA b\n---\n\nC\n\n"
+    );
+}
+
+#[test]
+fn toc_lists_heading_levels_and_text() {
+    let md =
+        "# The *first* one\n\ntext\n\n## `code` and [a link](https://example.com)\n\n> # Quoted\n";
+    assert_eq!(
+        prmd::build_toc(md),
+        [
+            (1, String::from("The first one")),
+            (2, String::from("code and a link")),
+            (1, String::from("Quoted")),
+        ]
+    );
+}
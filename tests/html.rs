@@ -0,0 +1,128 @@
+use prmd::{markdown_to_text_with, HtmlMode, RenderOptions};
+
+const MD: &str = "<div>\n  <p>Fish &amp; chips<br>&lt;b&gt; isn't bold</p>\n  <hr>\n</div>\n";
+
+fn render(html: HtmlMode) -> String {
+    let options = RenderOptions {
+        plain: true,
+        html,
+        ..RenderOptions::default()
+    };
+    markdown_to_text_with(MD, &options)
+}
+
+#[test]
+fn html_blocks_are_stripped_to_their_text() {
+    assert_eq!(
+        render(HtmlMode::Strip),
+        "Fish & chips\n<b> isn't bold\n───\n\n"
+    );
+}
+
+#[test]
+fn raw_html_blocks_are_printed_as_written() {
+    assert_eq!(render(HtmlMode::Raw), format!("{}\n", MD));
+}
+
+#[test]
+fn entities_are_decoded_once() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with(
+            "Fish &amp; chips &copy; &#169; &lt;b&gt; &amp;lt;\n",
+            &options
+        ),
+        "Fish & chips © © <b> &lt;\n\n"
+    );
+    let options = RenderOptions {
+        html: HtmlMode::Raw,
+        ..options
+    };
+    assert_eq!(
+        markdown_to_text_with("An <abbr title=\"Fish &amp; chips\">F&C</abbr>\n", &options),
+        "An <abbr title=\"Fish & chips\">F&C</abbr>\n\n"
+    );
+}
+
+#[test]
+fn details_are_expanded_under_their_summary() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let md = "<details>\n<summary>Click <b>me</b></summary>\n\nSome *text*.\n\n- item\n\n\
+              </details>\n\n<details><summary>More</summary>\nInline `code`\n</details>\n\nEnd\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "▸ Click me\n  Some text.\n\n  • item\n\n▸ More\n  Inline code\n\nEnd\n\n"
+    );
+}
+
+#[test]
+fn escaped_html_blocks_neutralize_harmful_tags() {
+    let options = RenderOptions {
+        plain: true,
+        html: HtmlMode::Escape,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("<div><script>alert(1)</script></div>\n", &options),
+        "<div>&lt;script>alert(1)&lt;/script></div>\n\n"
+    );
+}
+
+#[test]
+fn stripped_html_drops_tags_and_scripts() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("<div>\n<SCRIPT>alert(1)</SCRIPT>\nText\n</div>\n", &options),
+        "Text\n\n"
+    );
+    assert_eq!(
+        markdown_to_text_with("A <iframe src=\"x\"></iframe> and <b>b</b>\n", &options),
+        "A  and b\n\n"
+    );
+    assert_eq!(
+        markdown_to_text_with("A <script>alert(1)</script>b<br>c\n", &options),
+        "A b\nc\n\n"
+    );
+    assert_eq!(
+        markdown_to_text_with("> [!NOTE]\n> a <script>alert(1)</script> b\n", &options),
+        "│ ⓘ NOTE\n│ a  b\n\n"
+    );
+}
+
+#[test]
+fn escaped_inline_html_neutralizes_harmful_tags() {
+    let options = RenderOptions {
+        plain: true,
+        html: HtmlMode::Escape,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("A <iframe src=\"x\"></iframe> and <b>b</b>\n", &options),
+        "A &lt;iframe src=\"x\">&lt;/iframe> and <b>b</b>\n\n"
+    );
+}
+
+#[test]
+fn details_with_a_misplaced_summary_are_stripped() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("<details>x</summary> y <summary>z</details>\n", &options),
+        "x\ny\nz\n\n"
+    );
+    assert_eq!(
+        markdown_to_text_with("<details></summary><summary>\n", &options),
+        ""
+    );
+}
@@ -0,0 +1,43 @@
+use prmd::{markdown_to_text, markdown_to_text_with, RenderOptions};
+
+#[test]
+fn images_are_marked_apart_from_links() {
+    assert_eq!(
+        markdown_to_text("![alt](a.png \"Title\") [link](b.html)\n", true),
+        "🖼 alt \"Title\" [a.png] link [b.html]\n\n"
+    );
+}
+
+#[test]
+fn image_marker_is_configurable() {
+    let options = RenderOptions {
+        plain: true,
+        image_marker: String::from("image: "),
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("![alt](a.png)\n", &options),
+        "image: alt [a.png]\n\n"
+    );
+}
+
+#[test]
+fn images_can_be_left_out() {
+    let options = RenderOptions {
+        plain: true,
+        images: false,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("Text ![alt](a.png) and [a link](b.html).\n", &options),
+        "Text and a link [b.html].\n\n"
+    );
+    assert_eq!(
+        markdown_to_text_with("- ![icon](a.png) item\n", &options),
+        "• item\n\n"
+    );
+    assert_eq!(
+        markdown_to_text_with("| a | b |\n|---|---|\n| ![x](a.png) | 1 |\n", &options),
+        "a b \n- - \n  1 \n\n"
+    );
+}
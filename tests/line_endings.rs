@@ -0,0 +1,22 @@
+use prmd::{build_toc, markdown_to_text, markdown_to_text_with, HtmlMode, RenderOptions};
+
+#[test]
+fn a_byte_order_mark_is_dropped() {
+    assert_eq!(markdown_to_text("\u{feff}# Title\n", true), "Title\n\n");
+    assert_eq!(build_toc("\u{feff}# Title\n"), [(1, String::from("Title"))]);
+}
+
+#[test]
+fn crlf_and_cr_line_endings_leave_no_carriage_returns() {
+    let options = RenderOptions {
+        plain: true,
+        html: HtmlMode::Raw,
+        preserve_breaks: true,
+        ..RenderOptions::default()
+    };
+    let md = "# Title\r\n\r\none\r\ntwo\r\n\r\n```\r\ncode\r\n```\r\n\r\n<div>\r\nhi\r\n</div>\r\n\r\nold\rmac\r";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "Title\n\none\ntwo\n\n║ code\n\n<div>\nhi\n</div>\n\nold\nmac\n\n"
+    );
+}
@@ -0,0 +1,72 @@
+use prmd::{comrak_options, markdown_to_text, markdown_to_text_with_options, RenderOptions};
+
+#[test]
+fn reference_links_render_like_inline_links() {
+    let md = "See [the docs][docs], [docs] and [Docs][].\n\n[docs]: https://example.com\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "See the docs [https://example.com], docs [https://example.com] and Docs [https://example.com].\n\n"
+    );
+}
+
+#[test]
+fn reference_definitions_alone_render_nothing() {
+    assert_eq!(markdown_to_text("[docs]: https://example.com\n", true), "");
+}
+
+#[test]
+fn autolinks_show_their_url_once() {
+    assert_eq!(
+        markdown_to_text("See <https://example.com>.\n", true),
+        "See https://example.com.\n\n"
+    );
+    let mut options = comrak_options();
+    options.extension.autolink = true;
+    let render_options = RenderOptions {
+        plain: true,
+        link_references: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with_options("Go to https://example.com now\n", &options, &render_options),
+        "Go to https://example.com now\n\n"
+    );
+}
+
+#[test]
+fn wiki_links_show_their_page() {
+    let mut options = comrak_options();
+    options.extension.wikilinks_title_after_pipe = true;
+    let render_options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with_options(
+        "[[Home]] and [[Other page|that page]]\n",
+        &options,
+        &render_options,
+    );
+    assert_eq!(text, "Home and that page [Other page]\n\n");
+}
+
+#[test]
+fn email_autolinks_show_only_the_address() {
+    let md = "Write to <me@example.com> or [the list](mailto:list@example.com).\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "Write to me@example.com or the list [mailto:list@example.com].\n\n"
+    );
+    let options = RenderOptions {
+        hyperlinks: true,
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with_options("<me@example.com>\n", &comrak_options(), &options);
+    let address = options.theme.email.paint("me@example.com");
+    assert_eq!(
+        text,
+        format!(
+            "\x1b]8;;mailto:me@example.com\x1b\\{}\x1b]8;;\x1b\\\n\n",
+            address
+        )
+    );
+}
@@ -0,0 +1,128 @@
+use prmd::{markdown_to_text, markdown_to_text_with, RenderOptions};
+
+#[test]
+fn ordered_list_starting_at_zero() {
+    let md = "0. zero\n1. one\n2. two\n";
+    assert_eq!(markdown_to_text(md, true), "0. zero\n1. one\n2. two\n\n");
+}
+
+#[test]
+fn ordered_list_starting_at_large_number() {
+    let md = "998. first  \n     continued\n999. second\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "998. first\n     continued\n999. second\n\n"
+    );
+}
+
+#[test]
+fn ordered_list_numbers_are_right_aligned() {
+    let md = (1..=10)
+        .map(|number| format!("{}. item  \ncontinued\n", number))
+        .collect::<String>();
+    let text = markdown_to_text(&md, true);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], " 1. item");
+    assert_eq!(lines[1], "    continued");
+    assert_eq!(lines[18], "10. item");
+    assert_eq!(lines[19], "    continued");
+}
+
+#[test]
+fn nested_lists_use_the_configured_indent() {
+    let options = prmd::RenderOptions {
+        plain: true,
+        list_indent: 2,
+        ..prmd::RenderOptions::default()
+    };
+    let md = "- a\n  - b  \n    continued\n";
+    assert_eq!(
+        prmd::markdown_to_text_with(md, &options),
+        "• a\n  ◦ b\n    continued\n\n"
+    );
+}
+
+#[test]
+fn wrapped_items_hang_under_the_item_text() {
+    let options = prmd::RenderOptions {
+        plain: true,
+        width: Some(20),
+        ..prmd::RenderOptions::default()
+    };
+    let md = "- one two three four five\n  - [x] six seven eight nine\n";
+    assert_eq!(
+        prmd::markdown_to_text_with(md, &options),
+        "• one two three\n  four five\n    [x] six seven\n        eight nine\n\n"
+    );
+}
+
+#[test]
+fn nested_ordered_lists_number_from_their_own_start() {
+    let md = "3. a\n\n   7. x\n   8. y\n4. b\n   1. q\n   2. r\n      1. deep\n      2. deeper\n   3. s\n5. c\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "3. a\n    7. x\n    8. y\n4. b\n    1. q\n    2. r\n        1. deep\n        2. deeper\n    3. s\n5. c\n\n"
+    );
+}
+
+#[test]
+fn blocks_in_items_line_up_with_the_item_text() {
+    let md = "1. code:\n\n   ```\n   let x = 1;\n   ```\n\n   > quoted\n2. next\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "1. code:\n\n   ║ let x = 1;\n\n   │ quoted\n2. next\n\n"
+    );
+}
+
+#[test]
+fn later_paragraphs_of_an_item_have_no_marker() {
+    let md = "- first\n\n  second\n- third\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "• first\n\n  second\n• third\n\n"
+    );
+}
+
+#[test]
+fn empty_items_keep_their_marker() {
+    assert_eq!(markdown_to_text("1. one\n2.\n", true), "1. one\n2.\n\n");
+}
+
+#[test]
+fn task_lists_show_their_progress() {
+    let options = RenderOptions {
+        plain: true,
+        task_progress: true,
+        ..RenderOptions::default()
+    };
+    let md = "- [x] a\n- [ ] b\n  - [x] c\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "(1/2 done)\n[x] a\n[ ] b\n    (1/1 done)\n    [x] c\n\n"
+    );
+    // Lists with any items that aren't tasks are left alone
+    let md = "- [x] a\n- b\n";
+    assert_eq!(markdown_to_text_with(md, &options), "[x] a\n• b\n\n");
+}
+
+#[test]
+fn finished_task_lists_show_their_progress_as_checked() {
+    let options = RenderOptions {
+        task_progress: true,
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with("- [x] a\n", &options);
+    let progress = options.theme.task_checked.paint("(1/1 done)").to_string();
+    assert!(text.starts_with(&format!("{}\n", progress)), "{:?}", text);
+}
+
+#[test]
+fn bullets_change_with_each_nesting_level() {
+    let md = "- 1\n  - 2\n    - 3\n      - 4\n        - 5\n          - 6\n";
+    let text = markdown_to_text(md, true);
+    let bullets: Vec<&str> = text
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(bullets, ["•", "◦", "▪", "‣", "⁃", "•"]);
+}
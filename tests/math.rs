@@ -0,0 +1,17 @@
+use prmd::markdown_to_text;
+
+#[test]
+fn inline_math_keeps_its_delimiters_in_plain_mode() {
+    assert_eq!(
+        markdown_to_text("Inline $x^2$ and $`y`$.\n", true),
+        "Inline $x^2$ and $`y`$.\n\n"
+    );
+}
+
+#[test]
+fn display_math_is_indented() {
+    assert_eq!(
+        markdown_to_text("$$\na + b\n$$\n\nafter\n", true),
+        "    $$\n    a + b\n    $$\n\nafter\n\n"
+    );
+}
@@ -0,0 +1,92 @@
+use prmd::{markdown_to_text, markdown_to_text_with, RenderOptions};
+
+const MD: &str = "# Title\n\nSome *emphasis* and a [link](https://example.com).\n";
+
+#[test]
+fn markdown_to_text_matches_default_options() {
+    for plain in [false, true] {
+        let options = RenderOptions {
+            plain,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            markdown_to_text(MD, plain),
+            markdown_to_text_with(MD, &options)
+        );
+    }
+}
+
+#[test]
+fn written_markdown_matches_the_returned_text() {
+    for plain in [false, true] {
+        let mut written: Vec<u8> = vec![];
+        prmd::write_markdown(MD, plain, &mut written).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            markdown_to_text(MD, plain)
+        );
+    }
+    let options = RenderOptions {
+        plain: true,
+        width: Some(20),
+        link_references: true,
+        ..RenderOptions::default()
+    };
+    let mut written: Vec<u8> = vec![];
+    prmd::write_markdown_with(MD, &options, &mut written).unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        "Title\n\nSome emphasis and a\nlink[1].\n\nReferences\n\n[1] https://example.com\n\n"
+    );
+}
+
+#[test]
+fn comrak_options_control_parsing() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let md = "~~gone~~ www.example.com\n";
+    let mut comrak_options = prmd::comrak_options();
+    comrak_options.extension.strikethrough = false;
+    comrak_options.extension.autolink = true;
+    assert_eq!(
+        prmd::markdown_to_text_with_options(md, &comrak_options, &options),
+        "~~gone~~ www.example.com [http://www.example.com]\n\n"
+    );
+}
+
+#[test]
+fn superscripts_use_unicode_where_possible() {
+    let options = RenderOptions {
+        plain: true,
+        ..RenderOptions::default()
+    };
+    let mut comrak_options = prmd::comrak_options();
+    comrak_options.extension.superscript = true;
+    assert_eq!(
+        prmd::markdown_to_text_with_options("x^2n^ and 2^q^\n", &comrak_options, &options),
+        "x²ⁿ and 2^q\n\n"
+    );
+}
+
+#[test]
+fn blocks_are_rendered_one_at_a_time() {
+    let options = RenderOptions {
+        plain: true,
+        link_references: true,
+        ..RenderOptions::default()
+    };
+    let md = "# Title\n\nSome [text](url).\n\n- item\n";
+    let blocks: Vec<String> = prmd::render_blocks(md, &options).collect();
+    assert_eq!(
+        blocks,
+        [
+            "Title\n\n",
+            "Some text[1].\n\n",
+            "• item\n\n",
+            "References\n\n[1] url\n\n"
+        ]
+    );
+    assert_eq!(blocks.concat(), markdown_to_text_with(md, &options));
+}
@@ -0,0 +1,19 @@
+use prmd::markdown_to_plaintext;
+
+#[test]
+fn plaintext_leaves_out_urls_and_decorations() {
+    let md = "# Title\n\nA [link](https://example.com) and ![an image](a.png).\n\n> Quoted\n\n---\n\n```rust\nlet x = 1;\n```\n";
+    assert_eq!(
+        markdown_to_plaintext(md),
+        "Title\n\nA link and an image.\n\nQuoted\n\nlet x = 1;\n\n"
+    );
+}
+
+#[test]
+fn stats_count_the_text_without_markup() {
+    let stats =
+        prmd::document_stats("# Two words\n\nA [link](https://example.com/a/long/url) **here**.\n");
+    assert_eq!(stats.words, 5);
+    assert_eq!(stats.characters, "Two words A link here.".len());
+    assert_eq!(stats.reading_time, std::time::Duration::from_millis(1500));
+}
@@ -0,0 +1,45 @@
+use prmd::{markdown_to_text_with, RenderOptions, Renderer, Theme};
+
+const MD: &str = "# Title\n\nSome *text* with a [link](https://example.com).\n\n\
+                  | a | b |\n|---|---|\n| 1 | 2 |\n\n```rust\nfn main() {}\n```\n";
+
+#[test]
+fn builder_renders_like_the_matching_options() {
+    let renderer = Renderer::new()
+        .width(30)
+        .theme(Theme::light())
+        .hyperlinks(true)
+        .table_borders(true)
+        .highlight(false)
+        .link_references(true);
+    let options = RenderOptions {
+        width: Some(30),
+        theme: Theme::light(),
+        hyperlinks: true,
+        table_borders: true,
+        highlight: false,
+        link_references: true,
+        ..RenderOptions::default()
+    };
+    assert_eq!(renderer.render(MD), markdown_to_text_with(MD, &options));
+    let plain = RenderOptions {
+        plain: true,
+        ..options.clone()
+    };
+    assert_eq!(
+        renderer.clone().plain(true).render(MD),
+        markdown_to_text_with(MD, &plain)
+    );
+    assert_eq!(
+        Renderer::new().options(plain.clone()).render(MD),
+        markdown_to_text_with(MD, &plain)
+    );
+}
+
+#[test]
+fn written_text_matches_the_rendered_text() {
+    let renderer = Renderer::new().plain(true).width(20);
+    let mut written: Vec<u8> = vec![];
+    renderer.write(MD, &mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), renderer.render(MD));
+}
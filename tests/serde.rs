@@ -0,0 +1,50 @@
+#![cfg(feature = "serde")]
+
+use ansi_term::{Color, Style};
+use prmd::{HtmlMode, RenderOptions, Theme};
+
+#[test]
+fn options_survive_a_round_trip() {
+    let options = RenderOptions {
+        width: Some(60),
+        html: HtmlMode::Escape,
+        theme: Theme {
+            link: Color::RGB(0x11, 0x22, 0x33).underline(),
+            code: Style::new().fg(Color::Fixed(252)).on(Color::Black),
+            ..Theme::light()
+        },
+        ..RenderOptions::default()
+    };
+    let text = toml::to_string(&options).unwrap();
+    assert!(text.contains("html = \"escape\""), "{}", text);
+    assert!(text.contains("fg = \"#112233\""), "{}", text);
+    let read: RenderOptions = toml::from_str(&text).unwrap();
+    assert_eq!(read.width, Some(60));
+    assert_eq!(read.html, HtmlMode::Escape);
+    assert_eq!(read.theme, options.theme);
+}
+
+#[test]
+fn missing_options_and_styles_keep_their_defaults() {
+    let options: RenderOptions =
+        toml::from_str("plain = true\n\n[theme.emphasis]\nfg = \"cyan\"\n").unwrap();
+    assert!(options.plain);
+    assert_eq!(options.tab_width, RenderOptions::default().tab_width);
+    assert_eq!(
+        options.theme,
+        Theme {
+            emphasis: Color::Cyan.normal(),
+            ..Theme::default()
+        }
+    );
+}
+
+#[test]
+fn headings_need_a_style_for_each_level() {
+    let error = toml::from_str::<Theme>("headings = [{ bold = true }]\n").unwrap_err();
+    assert!(
+        error.to_string().contains("one style per level"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,93 @@
+use prmd::markdown_to_text;
+
+#[test]
+fn blank_lines_between_blocks_are_collapsed() {
+    let md = "para\n\n```\n```\n\n<div></div>\n\nnext\n";
+    assert_eq!(markdown_to_text(md, true), "para\n\nnext\n\n");
+}
+
+#[test]
+fn blank_lines_inside_code_blocks_are_kept() {
+    let md = "```\na\n\n\n\nb\n```\n";
+    assert_eq!(markdown_to_text(md, true), "║ a\n║ \n║ \n║ \n║ b\n\n");
+}
+
+#[test]
+fn left_margin_indents_every_line_within_the_width() {
+    let options = prmd::RenderOptions {
+        plain: true,
+        width: Some(14),
+        left_margin: 4,
+        ..prmd::RenderOptions::default()
+    };
+    let md = "one two three four\n\n| a | b |\n|---|---|\n| x | yy |\n";
+    assert_eq!(
+        prmd::markdown_to_text_with(md, &options),
+        "    one two\n    three four\n\n    a b  \n    - -- \n    x yy \n\n"
+    );
+}
+
+#[test]
+fn preserved_breaks_wrap_each_line() {
+    let options = prmd::RenderOptions {
+        plain: true,
+        width: Some(12),
+        preserve_breaks: true,
+        ..prmd::RenderOptions::default()
+    };
+    let md = "Roses are red and\nviolets\nare blue\n";
+    assert_eq!(
+        prmd::markdown_to_text_with(md, &options),
+        "Roses are\nred and\nviolets\nare blue\n\n"
+    );
+}
+
+#[test]
+fn nested_blockquotes_are_set_apart_from_their_siblings() {
+    let md = "> outer\n>\n> > inner\n>\n> after\n>\n> > again\n>\n> last\n";
+    assert_eq!(
+        markdown_to_text(md, true),
+        "│ outer\n│\n│ │ inner\n│\n│ after\n│\n│ │ again\n│\n│ last\n\n"
+    );
+}
+
+#[test]
+fn trailing_newlines_can_be_left_off() {
+    let options = prmd::RenderOptions {
+        plain: true,
+        trim_trailing_newlines: true,
+        ..prmd::RenderOptions::default()
+    };
+    assert_eq!(
+        prmd::markdown_to_text_with("# Title\n\nText\n\n---\n\n[a](b)\n", &options),
+        "Title\n\nText\n\n───\na [b]"
+    );
+    let options = prmd::RenderOptions {
+        link_references: true,
+        ..options
+    };
+    assert_eq!(
+        prmd::markdown_to_text_with("[a](b)\n", &options),
+        "a[1]\n\nReferences\n\n[1] b"
+    );
+}
+
+#[test]
+fn compact_text_has_no_blank_lines_between_blocks() {
+    let options = prmd::RenderOptions {
+        plain: true,
+        compact: true,
+        ..prmd::RenderOptions::default()
+    };
+    let md = "# Title\n\nText.\n\n> a\n>\n> b\n\n- x\n\n  more\n- y\n\n| a |\n|---|\n| 1 |\n";
+    assert_eq!(
+        prmd::markdown_to_text_with(md, &options),
+        "Title\nText.\n│ a\n│ b\n• x\n  more\n• y\na \n- \n1 \n"
+    );
+    // The blank lines inside code blocks are part of the code
+    let md = "```\nfirst\n\nsecond\n```\n\ntext\n";
+    assert_eq!(
+        prmd::markdown_to_text_with(md, &options),
+        "║ first\n║ \n║ second\ntext\n"
+    );
+}
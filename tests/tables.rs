@@ -0,0 +1,123 @@
+use ansi_term::{Color, Style};
+use prmd::{markdown_to_text_with, RenderOptions, Theme};
+
+#[test]
+fn tabs_in_cells_are_expanded_before_measuring() {
+    let options = RenderOptions {
+        plain: true,
+        tab_width: 4,
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with("| a\tb | c |\n|---|---|\n| x | y |\n", &options),
+        "a   b c \n----- - \nx     y \n\n"
+    );
+}
+
+#[test]
+fn rows_with_missing_or_extra_cells_keep_the_header_columns() {
+    let options = RenderOptions {
+        plain: true,
+        table_borders: true,
+        ..RenderOptions::default()
+    };
+    let md = "| a | b | c |\n|---|---|---|\n| 1 |\n| 1 | 2 | 3 | 4 |\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "┌───┬───┬───┐\n│ a │ b │ c │\n├───┼───┼───┤\n│ 1 │   │   │\n│ 1 │ 2 │ 3 │\n└───┴───┴───┘\n\n"
+    );
+}
+
+#[test]
+fn columns_align_with_wide_and_combining_characters() {
+    let options = RenderOptions {
+        plain: true,
+        table_borders: true,
+        ..RenderOptions::default()
+    };
+    let md = "| name | x |\n|---|---|\n| 日本語 | 1 |\n| café | 2 |\n| e\u{301}té | 3 |\n";
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "┌────────┬───┐\n│ name   │ x │\n├────────┼───┤\n│ 日本語 │ 1 │\n│ café   │ 2 │\n\
+         │ e\u{301}té    │ 3 │\n└────────┴───┘\n\n"
+    );
+}
+
+#[test]
+fn columns_align_with_images_and_hyperlinks() {
+    let md = "| a | b |\n|---|---|\n| ![alt](a.png) | x |\n| [link](https://example.com) | y |\n";
+    for (image_marker, width) in [("🖼 ", 21), ("🖼\u{fe0f} ", 22), ("[image] ", 27)] {
+        let options = RenderOptions {
+            hyperlinks: true,
+            table_borders: true,
+            image_marker: String::from(image_marker),
+            ..RenderOptions::default()
+        };
+        let text = markdown_to_text_with(md, &options);
+        let widths: Vec<usize> = text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(prmd::visible_width)
+            .collect();
+        assert!(
+            widths.iter().all(|line_width| *line_width == width),
+            "{:?}",
+            text
+        );
+    }
+}
+
+#[test]
+fn alternate_body_rows_are_striped_across_the_row() {
+    let stripe = Style::new().on(Color::Fixed(236));
+    let options = RenderOptions {
+        theme: Theme {
+            table_header: Style::new(),
+            table_stripe: stripe,
+            ..Theme::default()
+        },
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with(
+        "| a | b |\n|---|---|\n| 1 | x |\n| 2 | *y* |\n| 3 | z |\n",
+        &options,
+    );
+    let emphasis = Style::new().italic().paint("y").to_string();
+    let striped = format!("2 {}{} ", emphasis, stripe.prefix());
+    assert_eq!(
+        text,
+        format!("a b \n- - \n1 x \n{}\n3 z \n\n", stripe.paint(striped))
+    );
+}
+
+#[test]
+fn wide_tables_shrink_their_widest_columns_to_fit() {
+    let options = RenderOptions {
+        plain: true,
+        width: Some(30),
+        table_borders: true,
+        ..RenderOptions::default()
+    };
+    let md = "| Name | Description |\n|---|---|\n\
+              | short | a rather long description that goes on and on |\n\
+              | another name here | tiny |\n";
+    let text = markdown_to_text_with(md, &options);
+    let widths: Vec<usize> = text.lines().map(|line| line.chars().count()).collect();
+    assert!(widths[..widths.len() - 1].iter().all(|&width| width == 30));
+    assert!(text.contains("│ another name │ tiny        │\n│ here         │             │\n"));
+}
+
+#[test]
+fn numeric_columns_are_right_aligned_when_asked() {
+    let md = "| Item | Price | Note | Count |\n|---|---|--|:--|\n| Tea | $4.50 | 12 | 1 |\n| Cake | 1,200 | x | 20 |\n| Pie |  | 3 | 300 |\n";
+    let options = RenderOptions {
+        plain: true,
+        table_align_numbers: true,
+        ..RenderOptions::default()
+    };
+    // Columns with any text, and ones with their own alignment, are left as they are
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        "Item Price Note Count \n---- ----: ---- :---- \nTea  $4.50 12   1     \nCake 1,200 x    20    \nPie        3    300   \n\n"
+    );
+}
@@ -0,0 +1,98 @@
+use ansi_term::{Color, Style};
+use prmd::{markdown_to_text_with, RenderOptions, Theme};
+
+#[test]
+fn rgb_colors_are_downgraded_without_truecolor() {
+    let theme = Theme {
+        strong: Style::new()
+            .fg(Color::RGB(255, 0, 0))
+            .on(Color::RGB(18, 18, 18)),
+        emphasis: Color::Cyan.normal(),
+        ..Theme::default()
+    };
+    let theme = theme.to_256_colors();
+    assert_eq!(
+        theme.strong,
+        Style::new().fg(Color::Fixed(196)).on(Color::Fixed(233))
+    );
+    assert_eq!(theme.emphasis, Color::Cyan.normal());
+}
+
+#[test]
+fn truecolor_keeps_rgb_colors() {
+    let options = RenderOptions {
+        theme: Theme {
+            strong: Color::RGB(1, 2, 3).normal(),
+            ..Theme::default()
+        },
+        ..RenderOptions::default()
+    };
+    assert!(markdown_to_text_with("**a**", &options).contains("\x1b[38;2;1;2;3m"));
+    let options = RenderOptions {
+        truecolor: false,
+        ..options
+    };
+    assert!(markdown_to_text_with("**a**", &options).contains("\x1b[38;5;16m"));
+}
+
+#[cfg(feature = "theme-file")]
+#[test]
+fn themes_load_from_toml() {
+    let theme = Theme::from_toml(
+        "[heading1]\nfg = \"cyan\"\nbold = true\n\n[code]\nfg = 252\nbg = \"#202020\"\n",
+    )
+    .unwrap();
+    assert_eq!(theme.headings[0], Color::Cyan.bold());
+    assert_eq!(theme.code, Color::Fixed(252).on(Color::RGB(32, 32, 32)));
+    assert_eq!(theme.headings[1], Theme::default().headings[1]);
+}
+
+#[cfg(feature = "theme-file")]
+#[test]
+fn theme_files_reject_unknown_elements() {
+    let error = Theme::from_toml("[headng1]\nbold = true\n").unwrap_err();
+    assert!(error.to_string().contains("unknown field `headng1`"));
+}
+
+#[test]
+fn blockquote_leads_are_styled_by_level() {
+    let theme = Theme {
+        blockquote_lead: '┃',
+        blockquote_leads: vec![Color::Blue.normal(), Color::Cyan.normal()],
+        ..Theme::default()
+    };
+    let options = RenderOptions {
+        theme,
+        ..RenderOptions::default()
+    };
+    let text = markdown_to_text_with("> > > deep\n", &options);
+    let blue = Color::Blue.paint("┃ ").to_string();
+    let cyan = Color::Cyan.paint("┃ ").to_string();
+    assert_eq!(text, format!("{}{}{}deep\n\n", blue, cyan, blue));
+}
+
+#[test]
+fn colored_emphasis_uses_colors_instead_of_italic_and_bold() {
+    let md = "*a* **b**\n";
+    let options = RenderOptions {
+        theme: Theme::colored_emphasis(),
+        ..RenderOptions::default()
+    };
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        format!(
+            "{} {}\n\n",
+            Color::Cyan.paint("a"),
+            Color::Fixed(15).paint("b")
+        )
+    );
+    let options = RenderOptions::default();
+    assert_eq!(
+        markdown_to_text_with(md, &options),
+        format!(
+            "{} {}\n\n",
+            Style::new().italic().paint("a"),
+            Style::new().bold().paint("b")
+        )
+    );
+}
@@ -0,0 +1,25 @@
+#[test]
+fn visible_width_skips_escape_sequences() {
+    assert_eq!(prmd::visible_width("\x1b[1;4mbold\x1b[0m"), 4);
+    assert_eq!(
+        prmd::visible_width("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"),
+        4
+    );
+    assert_eq!(prmd::visible_width("日本"), 4);
+}
+
+#[test]
+fn strip_ansi_matches_plain_rendering() {
+    let md = "# Title\n\nSome *emphasis*, `code` and a [link](https://example.com).\n\n| a | b |\n|---|---|\n| **c** | d |\n";
+    assert_eq!(
+        prmd::strip_ansi(&prmd::markdown_to_text(md, false)),
+        prmd::markdown_to_text(md, true)
+    );
+}
+
+#[test]
+fn rendered_width_is_the_widest_line() {
+    let text = prmd::markdown_to_text("# Title\n\nA **longer** line\n", false);
+    assert_eq!(prmd::rendered_width(&text), 13);
+    assert_eq!(prmd::rendered_width(""), 0);
+}